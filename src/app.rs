@@ -1,23 +1,97 @@
+use crate::estimate::EstimateTotal;
+use crate::query::Query;
+use crate::report::Report;
+use crate::sort::Sort;
+use crate::stats::{Stats, TimeTotals};
 use crate::task::{Task, TaskStatus};
-use chrono::Local;
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
-use std::{fs, io, path::{Path, PathBuf}};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// How many changes can be undone.
+const MAX_UNDO: usize = 100;
+
+const TEXT_HEADER: &str = "\
+# One task per line: `[ ]` undone, `[-]` pending, `[x]` done.
+# Keep the `#id` prefix to update a task; lines without one are added
+# and tasks whose line is removed are deleted. Indented lines below a
+# task are its notes.
+";
+
+/// What changed when applying an edited task list, see [`TodoApp::apply_text`].
+#[derive(Default)]
+pub struct EditSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Tasks marked done in the text that were left open because they are blocked.
+    pub blocked: Vec<u64>,
+}
+
+/// A task line of edited text, with the indented note lines below it.
+struct EditedTask<'a> {
+    id: Option<u64>,
+    status: Option<TaskStatus>,
+    description: String,
+    notes: Vec<&'a str>,
+}
+
+impl fmt::Display for EditSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} deleted",
+            self.added, self.updated, self.deleted
+        )?;
+        if !self.blocked.is_empty() {
+            let ids: Vec<String> = self.blocked.iter().map(|id| format!("#{}", id)).collect();
+            write!(f, "; blocked, left open: {}", ids.join(", "))?;
+        }
+        Ok(())
+    }
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct TodoApp {
     pub tasks: Vec<Task>,
+    /// The id the next new task gets. Ids are never reused, so a `dep:` on a deleted task
+    /// does not end up pointing at another one.
+    #[serde(default)]
+    next_id: u64,
+    /// The task list before each change, most recent last.
+    #[serde(skip)]
+    undo_stack: Vec<Vec<Task>>,
+    /// The order kept by [`TodoApp::reorder_tasks`].
+    #[serde(skip)]
+    pub sort: Sort,
+    /// Whether [`TodoApp::completion_percentage`] weights tasks by their estimate.
+    #[serde(skip)]
+    pub weight_by_estimate: bool,
 }
 
 impl TodoApp {
     pub fn new() -> TodoApp {
-        TodoApp { tasks: vec![] }
+        TodoApp {
+            tasks: vec![],
+            next_id: 1,
+            undo_stack: vec![],
+            sort: Sort::default(),
+            weight_by_estimate: false,
+        }
     }
 
     pub fn load_from_file(filename: &Path) -> io::Result<TodoApp> {
         if filename.exists() {
             let content = fs::read_to_string(filename)?;
-            let tasks = serde_json::from_str(&content)?;
-            Ok(tasks)
+            let mut app: TodoApp = serde_json::from_str(&content)?;
+            app.assign_ids();
+            app.assign_positions();
+            Ok(app)
         } else {
             Ok(TodoApp::new())
         }
@@ -29,6 +103,64 @@ impl TodoApp {
         Ok(())
     }
 
+    /// Gives tasks saved before ids existed an id of their own, and starts counting after
+    /// the highest id in lists saved before the counter was.
+    fn assign_ids(&mut self) {
+        let max_id = self.tasks.iter().map(|t| t.id).max().unwrap_or(0);
+        self.next_id = self.next_id.max(max_id + 1);
+        for index in 0..self.tasks.len() {
+            if self.tasks[index].id == 0 {
+                self.tasks[index].id = self.next_id();
+            }
+        }
+    }
+
+    /// Makes manual positions unique, keeping their order. Lists saved before positions
+    /// existed keep the order they were saved in.
+    fn assign_positions(&mut self) {
+        let mut order: Vec<usize> = (0..self.tasks.len()).collect();
+        order.sort_by_key(|&i| self.tasks[i].position);
+        for (position, index) in order.into_iter().enumerate() {
+            self.tasks[index].position = position as u64;
+        }
+    }
+
+    fn next_position(&self) -> u64 {
+        self.tasks.iter().map(|t| t.position + 1).max().unwrap_or(0)
+    }
+
+    /// Remembers the current tasks so the change about to be made can be undone.
+    fn checkpoint(&mut self) {
+        self.undo_stack.push(self.tasks.clone());
+        if self.undo_stack.len() > MAX_UNDO {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    /// Reverts the most recent change. Returns whether there was one. Tracked time and
+    /// finished pomodoros are records rather than edits, so they are kept.
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(mut tasks) => {
+                for task in &mut tasks {
+                    if let Some(current) = self.tasks.iter_mut().find(|t| t.id == task.id) {
+                        task.pomodoros = current.pomodoros;
+                        task.time_log = std::mem::take(&mut current.time_log);
+                    }
+                }
+                self.tasks = tasks;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
     pub fn add_task(
         &mut self,
         description: String,
@@ -51,7 +183,8 @@ impl TodoApp {
             vec![description]
         };
 
-        let mut insert_index = match (&current_status, current_index) {
+        self.checkpoint();
+        let insert_index = match (&current_status, current_index) {
             (Some(TaskStatus::Pending | TaskStatus::Undone), Some(index)) => index + 1,
             _ => self
                 .tasks
@@ -61,94 +194,717 @@ impl TodoApp {
                 .unwrap_or(0),
         };
 
-        for task_description in tasks {
+        // New tasks take the positions right after the task before them
+        let first_position = insert_index
+            .checked_sub(1)
+            .and_then(|i| self.tasks.get(i))
+            .map_or(0, |t| t.position + 1);
+        let count = tasks.len() as u64;
+        for task in self.tasks.iter_mut() {
+            if task.position >= first_position {
+                task.position += count;
+            }
+        }
+
+        for (offset, task_description) in tasks.into_iter().enumerate() {
             let status = match current_status {
                 Some(TaskStatus::Pending) => TaskStatus::Pending,
                 _ => TaskStatus::Undone,
             };
 
-            let task = Task {
-                description: task_description,
-                status,
-                created_at: Some(Local::now()),
+            let mut task = Task::new(self.next_id(), task_description, status);
+            task.position = first_position + offset as u64;
+
+            self.tasks.insert(insert_index + offset, task);
+        }
+    }
+
+    /// The tasks with the given ids as editable text, one `#id [ ] description` per line.
+    pub fn to_text(&self, ids: &[u64]) -> String {
+        let mut text = TEXT_HEADER.to_string();
+        for task in self.tasks.iter().filter(|t| ids.contains(&t.id)) {
+            text.push_str(&format!(
+                "#{} {} {}\n",
+                task.id,
+                task.status.symbol(),
+                task.description
+            ));
+            for line in task.notes.lines() {
+                text.push_str(&format!("    {}\n", line));
+            }
+        }
+        text
+    }
+
+    /// Applies text produced by [`TodoApp::to_text`] after it was edited: lines with a known
+    /// id update their task, other lines add tasks, and tasks among `ids` without a line
+    /// are deleted.
+    pub fn apply_text(&mut self, text: &str, ids: &[u64]) -> EditSummary {
+        let mut summary = EditSummary::default();
+        let mut edited: Vec<EditedTask> = Vec::new();
+
+        for line in text.lines() {
+            if line.starts_with([' ', '\t']) {
+                if let Some(task) = edited.last_mut() {
+                    let note = line.strip_prefix("    ").unwrap_or(line.trim_start());
+                    task.notes.push(note.trim_end());
+                }
+                continue;
+            }
+            let mut line = line.trim();
+            let id = match line.strip_prefix('#') {
+                Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => {
+                    let end = rest
+                        .find(|c: char| !c.is_ascii_digit())
+                        .unwrap_or(rest.len());
+                    line = rest[end..].trim_start();
+                    rest[..end].parse::<u64>().ok()
+                }
+                Some(_) => continue, // Comment
+                None => None,
             };
+            let status = line.get(..3).and_then(TaskStatus::from_symbol);
+            if status.is_some() {
+                line = line[3..].trim_start();
+            }
+            if !line.is_empty() {
+                edited.push(EditedTask {
+                    id,
+                    status,
+                    description: line.to_string(),
+                    notes: Vec::new(),
+                });
+            }
+        }
 
-            self.tasks.insert(insert_index, task);
-            insert_index += 1;
+        self.checkpoint();
+        let mut seen = Vec::new();
+        // Tasks marked done by the edit, with the status they had before
+        let mut completed = Vec::new();
+        for EditedTask {
+            id,
+            status,
+            description,
+            notes,
+        } in edited
+        {
+            let notes = notes.join("\n").trim_end().to_string();
+            let existing = id
+                .filter(|id| ids.contains(id) && !seen.contains(id))
+                .and_then(|id| self.tasks.iter_mut().find(|t| t.id == id));
+            match existing {
+                Some(task) => {
+                    seen.push(task.id);
+                    let status = status.unwrap_or_else(|| task.status.clone());
+                    if task.description != description
+                        || task.status != status
+                        || task.notes != notes
+                    {
+                        if status == TaskStatus::Done && task.status != TaskStatus::Done {
+                            completed.push((task.id, task.status.clone()));
+                        }
+                        task.description = description;
+                        task.set_status(status);
+                        task.notes = notes;
+                        task.touch();
+                        summary.updated += 1;
+                    }
+                }
+                None => {
+                    let status = status.unwrap_or(TaskStatus::Undone);
+                    let mut task = Task::new(self.next_id(), description, status);
+                    if task.status == TaskStatus::Done {
+                        completed.push((task.id, TaskStatus::Undone));
+                    }
+                    task.position = self.next_position();
+                    task.notes = notes;
+                    seen.push(task.id);
+                    self.tasks.push(task);
+                    summary.added += 1;
+                }
+            }
+        }
+
+        let before = self.tasks.len();
+        self.tasks
+            .retain(|t| !ids.contains(&t.id) || seen.contains(&t.id));
+        summary.deleted = before - self.tasks.len();
+
+        // Blocked tasks cannot be completed, going by the dependencies after the edit.
+        // Reopening one can block another, so repeat until nothing changes.
+        while let Some(position) = completed.iter().position(|(id, _)| {
+            self.tasks
+                .iter()
+                .position(|t| t.id == *id)
+                .is_some_and(|index| self.is_blocked(index))
+        }) {
+            let (id, status) = completed.remove(position);
+            if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+                task.set_status(status);
+            }
+            summary.blocked.push(id);
+        }
+        summary.blocked.sort_unstable();
+        self.reorder_tasks();
+        summary
+    }
+
+    pub fn set_notes(&mut self, index: usize, notes: String) {
+        if index < self.tasks.len() {
+            self.checkpoint();
+            let task = &mut self.tasks[index];
+            task.notes = notes.trim_end().to_string();
+            task.touch();
         }
     }
 
     pub fn delete_task(&mut self, index: usize) {
         if index < self.tasks.len() {
+            self.checkpoint();
             self.tasks.remove(index);
         }
     }
 
     pub fn remove_done_tasks(&mut self) {
+        self.checkpoint();
         self.tasks.retain(|task| task.status != TaskStatus::Done);
     }
 
     pub fn edit_task(&mut self, index: usize, new_description: String) {
-        if let Some(task) = self.tasks.get_mut(index) {
-            task.description = new_description;
+        if index < self.tasks.len() {
+            self.checkpoint();
+            self.tasks[index].description = new_description;
+            self.tasks[index].touch();
         }
     }
 
-    pub fn toggle_task(&mut self, index: usize) {
-        if let Some(task) = self.tasks.get_mut(index) {
-            task.status = match task.status {
+    /// Toggles a task between undone and done. Returns `false` when it is left undone
+    /// because it is blocked.
+    pub fn toggle_task(&mut self, index: usize) -> bool {
+        if index < self.tasks.len() {
+            if self.tasks[index].status == TaskStatus::Undone && self.is_blocked(index) {
+                return false;
+            }
+            self.checkpoint();
+            let task = &mut self.tasks[index];
+            task.set_status(match task.status {
                 TaskStatus::Undone => TaskStatus::Done,
                 TaskStatus::Pending => TaskStatus::Undone,
                 TaskStatus::Done => TaskStatus::Undone,
-            };
+            });
             self.reorder_tasks();
         }
+        true
     }
 
     pub fn toggle_pending(&mut self, index: usize) {
-        if let Some(task) = self.tasks.get_mut(index) {
-            task.status = match task.status {
+        if index < self.tasks.len() {
+            self.checkpoint();
+            let task = &mut self.tasks[index];
+            task.set_status(match task.status {
                 TaskStatus::Undone => TaskStatus::Pending,
                 TaskStatus::Pending => TaskStatus::Undone,
                 TaskStatus::Done => TaskStatus::Pending,
-            };
+            });
+            self.reorder_tasks();
+        }
+    }
+
+    /// Returns `false` when the task is blocked and so not marked done.
+    pub fn set_status(&mut self, index: usize, status: TaskStatus) -> bool {
+        if index < self.tasks.len() {
+            if status == TaskStatus::Done && self.is_blocked(index) {
+                return false;
+            }
+            self.checkpoint();
+            self.tasks[index].set_status(status);
             self.reorder_tasks();
         }
+        true
+    }
+
+    /// Marks the tasks with the given ids done, or undone when all of them already are.
+    /// Returns how many were left as they are because they are blocked.
+    pub fn toggle_tasks(&mut self, ids: &[u64]) -> usize {
+        self.set_status_toggling(ids, TaskStatus::Done)
+    }
+
+    /// Marks the tasks with the given ids pending, or undone when all of them already are.
+    pub fn toggle_pending_tasks(&mut self, ids: &[u64]) {
+        self.set_status_toggling(ids, TaskStatus::Pending);
+    }
+
+    fn set_status_toggling(&mut self, ids: &[u64], status: TaskStatus) -> usize {
+        let all_set = self
+            .tasks
+            .iter()
+            .filter(|t| ids.contains(&t.id))
+            .all(|t| t.status == status);
+        let status = if all_set { TaskStatus::Undone } else { status };
+        // Blocked tasks cannot be completed
+        let blocked: Vec<u64> = if status == TaskStatus::Done {
+            (0..self.tasks.len())
+                .filter(|&i| self.tasks[i].status != TaskStatus::Done && self.is_blocked(i))
+                .map(|i| self.tasks[i].id)
+                .filter(|id| ids.contains(id))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.checkpoint();
+        for task in self
+            .tasks
+            .iter_mut()
+            .filter(|t| ids.contains(&t.id) && !blocked.contains(&t.id))
+        {
+            task.set_status(status.clone());
+        }
+        self.reorder_tasks();
+        blocked.len()
+    }
+
+    /// Whether the task has dependencies that are not done yet.
+    pub fn is_blocked(&self, index: usize) -> bool {
+        !self.tasks[index].blockers(&self.tasks).is_empty()
+    }
+
+    /// Task ids that depend on each other in a circle, starting and ending with the same
+    /// id, e.g. `[1, 2, 1]`.
+    pub fn dependency_cycle(&self) -> Option<Vec<u64>> {
+        fn visit(app: &TodoApp, id: u64, path: &mut Vec<u64>, checked: &mut HashSet<u64>) -> bool {
+            if let Some(start) = path.iter().position(|&i| i == id) {
+                path.drain(..start);
+                path.push(id);
+                return true;
+            }
+            if !checked.insert(id) {
+                return false;
+            }
+            path.push(id);
+            let dependencies = app
+                .tasks
+                .iter()
+                .find(|t| t.id == id)
+                .map(Task::dependencies)
+                .unwrap_or_default();
+            if dependencies
+                .into_iter()
+                .any(|dependency| visit(app, dependency, path, checked))
+            {
+                return true;
+            }
+            path.pop();
+            false
+        }
+
+        let mut checked = HashSet::new();
+        self.tasks.iter().find_map(|task| {
+            let mut path = Vec::new();
+            visit(self, task.id, &mut path, &mut checked).then_some(path)
+        })
+    }
+
+    /// Starts the timer of a task, stopping the one running on any other task, or stops it
+    /// when it already runs. Returns whether the timer is running now. Not an undo step, as
+    /// undo keeps the tracked time.
+    pub fn toggle_timer(&mut self, index: usize) -> bool {
+        if index >= self.tasks.len() {
+            return false;
+        }
+        if self.tasks[index].stop_timer().is_some() {
+            return false;
+        }
+        for task in &mut self.tasks {
+            task.stop_timer();
+        }
+        self.tasks[index].start_timer();
+        true
+    }
+
+    /// Snoozes the tasks with the given ids until `until`, or wakes them up with `None`.
+    pub fn snooze_tasks(&mut self, ids: &[u64], until: Option<DateTime<Local>>) {
+        self.checkpoint();
+        for task in self.tasks.iter_mut().filter(|t| ids.contains(&t.id)) {
+            task.wait = until;
+            task.touch();
+        }
+    }
+
+    /// Ends the snooze of tasks whose time has come, returning their descriptions. Not an
+    /// undo step, as it is not an edit.
+    pub fn wake_up(&mut self, now: DateTime<Local>) -> Vec<String> {
+        self.tasks
+            .iter_mut()
+            .filter(|task| task.wait.is_some_and(|wait| wait <= now))
+            .map(|task| {
+                task.wait = None;
+                task.description.clone()
+            })
+            .collect()
+    }
+
+    pub fn snoozed_count(&self, now: DateTime<Local>) -> usize {
+        self.tasks.iter().filter(|t| t.is_snoozed(now)).count()
+    }
+
+    /// Counts a finished pomodoro. Not an undo step, as it is not an edit.
+    pub fn add_pomodoro(&mut self, id: u64) {
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+            task.pomodoros += 1;
+        }
+    }
+
+    /// The task whose timer runs, if any.
+    pub fn running_timer(&self) -> Option<&Task> {
+        self.tasks
+            .iter()
+            .find(|task| task.timer_started().is_some())
+    }
+
+    pub fn delete_tasks(&mut self, ids: &[u64]) {
+        self.checkpoint();
+        self.tasks.retain(|t| !ids.contains(&t.id));
+    }
+
+    /// Adds `@tag` to the tasks with the given ids that do not have it yet.
+    pub fn tag_tasks(&mut self, ids: &[u64], tag: &str) {
+        let tag = tag.trim().trim_start_matches('@');
+        self.edit_descriptions(ids, |description| {
+            if !description
+                .split_whitespace()
+                .any(|w| w == format!("@{}", tag))
+            {
+                description.push_str(&format!(" @{}", tag));
+            }
+        });
     }
 
+    /// Sets the `(A)` priority of the tasks with the given ids, or removes it for `None`.
+    pub fn prioritize_tasks(&mut self, ids: &[u64], priority: Option<char>) {
+        self.edit_descriptions(ids, |description| {
+            let mut chars = description.chars();
+            if let (Some('('), Some(p), Some(')')) = (chars.next(), chars.next(), chars.next()) {
+                if p.is_ascii_uppercase() {
+                    *description = description[3..].trim_start().to_string();
+                }
+            }
+            if let Some(p) = priority {
+                *description = format!("({}) {}", p, description);
+            }
+        });
+    }
+
+    /// Moves the tasks with the given ids to `+project`, replacing their other projects.
+    pub fn move_tasks(&mut self, ids: &[u64], project: &str) {
+        let project = project.trim().trim_start_matches('+');
+        self.edit_descriptions(ids, |description| {
+            let mut words: Vec<String> = description
+                .split_whitespace()
+                .filter(|w| !(w.starts_with('+') && w.len() > 1))
+                .map(str::to_string)
+                .collect();
+            if !project.is_empty() {
+                words.push(format!("+{}", project));
+            }
+            *description = words.join(" ");
+        });
+    }
+
+    fn edit_descriptions(&mut self, ids: &[u64], edit: impl Fn(&mut String)) {
+        self.checkpoint();
+        for task in self.tasks.iter_mut().filter(|t| ids.contains(&t.id)) {
+            let before = task.description.clone();
+            edit(&mut task.description);
+            if task.description != before {
+                task.touch();
+            }
+        }
+    }
+
+    /// Swaps the manual positions of two tasks, e.g. to move a task past its neighbor. While
+    /// grouped by status, only tasks with the same status can swap. Returns whether they
+    /// were swapped.
+    pub fn swap_tasks(&mut self, id: u64, other_id: u64) -> bool {
+        let index = self.tasks.iter().position(|t| t.id == id);
+        let other_index = self.tasks.iter().position(|t| t.id == other_id);
+        let (Some(index), Some(other_index)) = (index, other_index) else {
+            return false;
+        };
+        let different_status = self.tasks[index].status != self.tasks[other_index].status;
+        if index == other_index || (self.sort.group_by_status && different_status) {
+            return false;
+        }
+        self.checkpoint();
+        let position = self.tasks[index].position;
+        self.tasks[index].position = self.tasks[other_index].position;
+        self.tasks[other_index].position = position;
+        self.reorder_tasks();
+        true
+    }
+
+    pub fn set_sort(&mut self, sort: Sort) {
+        self.sort = sort;
+        self.reorder_tasks();
+    }
+
+    /// Sorts by status unless grouping is turned off, then by the sort key, falling back
+    /// to the manual order.
     pub fn reorder_tasks(&mut self) {
-        self.tasks.sort_by_key(|t| match t.status {
+        let sort = self.sort;
+        let status = |t: &Task| match t.status {
             TaskStatus::Undone => 0,
             TaskStatus::Pending => 1,
             TaskStatus::Done => 2,
+        };
+        self.tasks.sort_by(|a, b| {
+            let group = if sort.group_by_status {
+                status(a).cmp(&status(b))
+            } else {
+                Ordering::Equal
+            };
+            group
+                .then_with(|| sort.compare(a, b))
+                .then(a.position.cmp(&b.position))
         });
     }
 
+    /// Tasks matching a filter written in the query language of [`Query`]. A query that
+    /// fails to parse falls back to a plain substring match.
     pub fn filter_tasks(&self, query: &str) -> Vec<Task> {
+        let query = Query::parse(query).unwrap_or_else(|_| Query::text(query));
         self.tasks
             .iter()
-            .filter(|task| task.description.contains(query))
+            .filter(|task| query.matches(task, &self.tasks))
             .cloned()
             .collect()
     }
 
+    pub fn stats(&self) -> Stats {
+        Stats::of(&self.tasks, Local::now().date_naive())
+    }
+
+    pub fn time_totals(&self) -> TimeTotals {
+        TimeTotals::of(&self.tasks, Local::now())
+    }
+
+    /// What was completed, created, is still open or overdue since the given time.
+    pub fn report(&self, since: DateTime<Local>) -> Report<'_> {
+        Report::of(&self.tasks, since, Local::now().date_naive())
+    }
+
     pub fn completion_percentage(&self) -> f32 {
-        let done_count = self
-            .tasks
-            .iter()
-            .filter(|t| t.status == TaskStatus::Done)
-            .count();
-        let undone_count = self
-            .tasks
-            .iter()
-            .filter(|t| t.status == TaskStatus::Undone)
-            .count();
-        let total_count = done_count + undone_count;
+        self.completion_percentage_of(&self.tasks)
+    }
+
+    /// Share of done tasks among done and undone ones; pending tasks are not counted.
+    /// With `weight_by_estimate` each task counts with its estimate, and tasks without one
+    /// as an hour or a point.
+    pub fn completion_percentage_of(&self, tasks: &[Task]) -> f32 {
+        let weight = |task: &Task| match task.estimate() {
+            Some(estimate) if self.weight_by_estimate => estimate.weight(),
+            _ => 1.0,
+        };
+        let sum = |status: TaskStatus| -> f32 {
+            tasks
+                .iter()
+                .filter(|t| t.status == status)
+                .map(weight)
+                .sum()
+        };
+        let done = sum(TaskStatus::Done);
+        let total = done + sum(TaskStatus::Undone);
 
-        if total_count == 0 {
+        if total == 0.0 {
             0.0
         } else {
-            (done_count as f32 / total_count as f32) * 100.0
+            (done / total) * 100.0
+        }
+    }
+
+    /// Estimates of the given tasks with the given status.
+    pub fn remaining_estimate(tasks: &[Task], status: TaskStatus) -> EstimateTotal {
+        tasks
+            .iter()
+            .filter(|t| t.status == status)
+            .filter_map(Task::estimate)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An app with an undone task for each description, with ids counting from 1.
+    fn app_with(descriptions: &[&str]) -> TodoApp {
+        let mut app = TodoApp::new();
+        for description in descriptions {
+            app.add_task(description.to_string(), None, None);
         }
+        app
+    }
+
+    fn task(app: &TodoApp, id: u64) -> &Task {
+        app.tasks.iter().find(|t| t.id == id).unwrap()
+    }
+
+    /// The tasks as `#id [ ] description` lines, in id order.
+    fn lines(app: &TodoApp) -> Vec<String> {
+        let mut tasks: Vec<&Task> = app.tasks.iter().collect();
+        tasks.sort_by_key(|t| t.id);
+        tasks
+            .iter()
+            .map(|t| format!("#{} {} {}", t.id, t.status.symbol(), t.description))
+            .collect()
+    }
+
+    #[test]
+    fn edited_lines_update_their_task() {
+        let mut app = app_with(&["Write docs", "Buy milk"]);
+        let text = app.to_text(&[1, 2]);
+        let text = text.replace("[ ] Write docs", "[x] Write the docs");
+        let summary = app.apply_text(&text, &[1, 2]);
+        assert_eq!(summary.to_string(), "0 added, 1 updated, 0 deleted");
+        assert_eq!(lines(&app), ["#1 [x] Write the docs", "#2 [ ] Buy milk"]);
+    }
+
+    #[test]
+    fn lines_without_an_id_or_status_add_tasks() {
+        let mut app = app_with(&["Write docs"]);
+        let text = app.to_text(&[1]) + "[-] Call Bob\nRead a book\n\n";
+        let summary = app.apply_text(&text, &[1]);
+        assert_eq!(summary.to_string(), "2 added, 0 updated, 0 deleted");
+        assert_eq!(
+            lines(&app),
+            ["#1 [ ] Write docs", "#2 [-] Call Bob", "#3 [ ] Read a book"]
+        );
+    }
+
+    #[test]
+    fn lines_starting_with_hash_and_no_digit_are_comments() {
+        let mut app = app_with(&["Write docs"]);
+        let text = app.to_text(&[1]) + "# A comment\n#tag is not an id\n";
+        let summary = app.apply_text(&text, &[1]);
+        assert_eq!(summary.to_string(), "0 added, 0 updated, 0 deleted");
+        assert_eq!(lines(&app), ["#1 [ ] Write docs"]);
+    }
+
+    #[test]
+    fn removed_lines_delete_their_task() {
+        let mut app = app_with(&["Write docs", "Buy milk", "Not shown"]);
+        let text = app.to_text(&[1, 2]).replace("#2 [ ] Buy milk\n", "");
+        let summary = app.apply_text(&text, &[1, 2]);
+        assert_eq!(summary.to_string(), "0 added, 0 updated, 1 deleted");
+        // Tasks that were not in the text stay
+        assert_eq!(lines(&app), ["#1 [ ] Write docs", "#3 [ ] Not shown"]);
+    }
+
+    #[test]
+    fn a_repeated_id_adds_a_copy() {
+        let mut app = app_with(&["Write docs"]);
+        let text = app.to_text(&[1]) + "#1 [ ] Write more docs\n#7 [ ] Unknown id\n";
+        let summary = app.apply_text(&text, &[1]);
+        assert_eq!(summary.to_string(), "2 added, 0 updated, 0 deleted");
+        assert_eq!(
+            lines(&app),
+            [
+                "#1 [ ] Write docs",
+                "#2 [ ] Write more docs",
+                "#3 [ ] Unknown id"
+            ]
+        );
+    }
+
+    #[test]
+    fn indented_lines_are_notes_of_the_task_above() {
+        let mut app = app_with(&["Write docs", "Buy milk"]);
+        let text = app.to_text(&[1, 2]).replace(
+            "#1 [ ] Write docs\n",
+            "#1 [ ] Write docs\n    Intro\n    \n\t  Usage\n    \n",
+        );
+        let summary = app.apply_text(&text, &[1, 2]);
+        assert_eq!(summary.to_string(), "0 added, 1 updated, 0 deleted");
+        // Blank lines between notes stay, trailing ones go
+        assert_eq!(task(&app, 1).notes, "Intro\n\nUsage");
+        assert_eq!(task(&app, 2).notes, "");
+        // Notes come back indented, so the text stays the same
+        let text = app.to_text(&[1, 2]);
+        let summary = app.apply_text(&text, &[1, 2]);
+        assert_eq!(summary.to_string(), "0 added, 0 updated, 0 deleted");
+    }
+
+    #[test]
+    fn blocked_tasks_stay_open() {
+        let mut app = app_with(&["Design", "Build dep:1", "Ship dep:2", "Test"]);
+        let ids = [1, 2, 3, 4];
+        // Reopening Build blocks Ship and Release again
+        let text = app
+            .to_text(&ids)
+            .replace("[ ] Build", "[x] Build")
+            .replace("[ ] Ship", "[x] Ship")
+            .replace("[ ] Test", "[x] Test")
+            + "[x] Release dep:3\n";
+        let summary = app.apply_text(&text, &ids);
+        assert_eq!(
+            summary.to_string(),
+            "1 added, 3 updated, 0 deleted; blocked, left open: #2, #3, #5"
+        );
+        assert_eq!(
+            lines(&app),
+            [
+                "#1 [ ] Design",
+                "#2 [ ] Build dep:1",
+                "#3 [ ] Ship dep:2",
+                "#4 [x] Test",
+                "#5 [ ] Release dep:3"
+            ]
+        );
+
+        // Completing the whole chain at once is fine
+        let ids = [1, 2, 3, 4, 5];
+        let text = app.to_text(&ids).replace("[ ]", "[x]");
+        let summary = app.apply_text(&text, &ids);
+        assert!(summary.blocked.is_empty());
+        assert!(app.tasks.iter().all(|t| t.status == TaskStatus::Done));
+    }
+
+    #[test]
+    fn dependency_cycles_are_found_from_any_task() {
+        let cycle = |descriptions: &[&str]| app_with(descriptions).dependency_cycle();
+        assert_eq!(cycle(&["a", "b", "c", "d", "e dep:5"]), Some(vec![5, 5]));
+        assert_eq!(cycle(&["a dep:2", "b dep:1"]), Some(vec![1, 2, 1]));
+        assert_eq!(
+            cycle(&["a dep:2", "b dep:3", "c dep:1"]),
+            Some(vec![1, 2, 3, 1])
+        );
+        // The path leading into the cycle is not part of it
+        assert_eq!(
+            cycle(&["a dep:2", "b dep:3", "c dep:2"]),
+            Some(vec![2, 3, 2])
+        );
+        // Tasks reached twice are no cycle
+        assert_eq!(cycle(&["a dep:2,3", "b dep:4", "c dep:4", "d"]), None);
+        assert_eq!(cycle(&["a dep:9", "b"]), None);
+    }
+
+    #[test]
+    fn blocked_tasks_cannot_be_completed() {
+        let mut app = app_with(&["Design", "Build dep:1", "Ship dep:1,2"]);
+        let index = |app: &TodoApp, id| app.tasks.iter().position(|t| t.id == id).unwrap();
+        assert!(app.is_blocked(index(&app, 2)));
+        assert!(!app.toggle_task(index(&app, 2)));
+        assert!(!app.set_status(index(&app, 2), TaskStatus::Done));
+        assert_eq!(task(&app, 2).status.symbol(), "[ ]");
+        // Other changes are fine
+        assert!(app.set_status(index(&app, 2), TaskStatus::Pending));
+
+        // Completing several leaves the blocked ones as they are
+        assert_eq!(app.toggle_tasks(&[1, 2, 3]), 2);
+        assert_eq!(lines(&app)[..2], ["#1 [x] Design", "#2 [-] Build dep:1"]);
+        assert_eq!(task(&app, 3).status.symbol(), "[ ]");
+        assert_eq!(app.toggle_tasks(&[2, 3]), 1);
+        assert_eq!(task(&app, 2).status.symbol(), "[x]");
+        assert!(app.toggle_task(index(&app, 3)));
+        assert_eq!(task(&app, 3).status.symbol(), "[x]");
     }
 }
@@ -0,0 +1,67 @@
+use crate::app::TodoApp;
+use crate::query::{self, Query};
+use crate::report::Format;
+use std::path::Path;
+
+const USAGE: &str =
+    "Usage: todo [list [QUERY] | stats | time | report [--since PERIOD] [--markdown]]";
+
+/// Runs a non-interactive subcommand, e.g. `todo list status:pending +work`.
+pub fn run(args: &[String], todo_file_path: &Path) -> Result<(), String> {
+    let app = TodoApp::load_from_file(todo_file_path).unwrap_or_else(|_| TodoApp::new());
+
+    match args.first().map(String::as_str) {
+        Some("list") => list(&app, &args[1..].join(" ")),
+        Some("stats") => {
+            print!("{}", app.stats());
+            Ok(())
+        }
+        Some("time") => {
+            print!("{}", app.time_totals());
+            Ok(())
+        }
+        Some("report") => report(&app, &args[1..]),
+        Some("help" | "-h" | "--help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(format!("Unknown command '{}'\n{}", command, USAGE)),
+        None => Ok(()),
+    }
+}
+
+fn list(app: &TodoApp, query: &str) -> Result<(), String> {
+    let query = Query::parse(query).map_err(|err| format!("Invalid query: {}", err))?;
+    for task in app
+        .tasks
+        .iter()
+        .filter(|task| query.matches(task, &app.tasks))
+    {
+        println!("{} {}", task.status.symbol(), task.description);
+        for line in task.notes.lines() {
+            println!("    {}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Prints a report, e.g. `todo report --since 7d --markdown`. The period defaults to a week.
+fn report(app: &TodoApp, args: &[String]) -> Result<(), String> {
+    let mut since = "7d";
+    let mut format = Format::Text;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--since" => {
+                since = args
+                    .next()
+                    .ok_or_else(|| format!("Missing period after --since\n{}", USAGE))?;
+            }
+            "--markdown" => format = Format::Markdown,
+            _ => return Err(format!("Unknown option '{}'\n{}", arg, USAGE)),
+        }
+    }
+    let since = query::parse_date(since, false).map_err(|err| err.to_string())?;
+    print!("{}", app.report(since).render(format));
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use crate::keymap::{Action, KeySpec};
+use crate::pomodoro::PomodoroConfig;
+use crate::theme::ThemeSpec;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// User settings read from `~/.config/todo/config.toml`.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Config {
+    pub views: Vec<SavedView>,
+    pub keys: HashMap<Action, KeySpec>,
+    pub theme: Option<String>,
+    pub themes: HashMap<String, ThemeSpec>,
+    pub pomodoro: PomodoroConfig,
+    /// Weight the completion percentage by the `est:` estimates of tasks.
+    pub weight_by_estimate: bool,
+}
+
+/// A named filter query shown as a tab above the task list.
+#[derive(Deserialize, Clone)]
+pub struct SavedView {
+    pub name: String,
+    pub query: String,
+}
+
+pub fn get_config_file_path() -> PathBuf {
+    let config_dir = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home_dir = env::var("HOME").expect("Unable to get $HOME directory");
+            Path::new(&home_dir).join(".config")
+        }
+    };
+    config_dir.join("todo").join("config.toml")
+}
+
+impl Config {
+    /// Loads the config, falling back to defaults when the file does not exist.
+    pub fn load_from_file(path: &Path) -> Result<Config, String> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let mut config: Config =
+            toml::from_str(&content).map_err(|err| format!("Invalid config: {}", err))?;
+        config.pomodoro = config.pomodoro.clamped();
+        Ok(config)
+    }
+
+    /// Appends a view to the config file, leaving the rest of the file untouched.
+    pub fn append_view(path: &Path, view: &SavedView) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(
+            file,
+            "\n[[views]]\nname = {}\nquery = {}",
+            toml::Value::from(view.name.as_str()),
+            toml::Value::from(view.query.as_str())
+        )
+    }
+}
@@ -0,0 +1,114 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::UnicodeWidthStr;
+
+/// A single-line text editor with a cursor, used by the input box.
+#[derive(Default)]
+pub struct LineEditor {
+    text: String,
+    /// Byte offset of the cursor in `text`, always on a char boundary.
+    cursor: usize,
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text and moves the cursor to its end.
+    pub fn set(&mut self, text: String) {
+        self.cursor = text.len();
+        self.text = text;
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// Display width of the text before the cursor.
+    pub fn cursor_width(&self) -> usize {
+        self.text[..self.cursor].width()
+    }
+
+    /// Inserts text at the cursor; line breaks of pasted text become spaces.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text.replace(['\r', '\n'], " ");
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Applies an editing key and returns whether it was handled.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.text.len(),
+            KeyCode::Char('b') if ctrl => self.cursor = self.prev_char(),
+            KeyCode::Char('f') if ctrl => self.cursor = self.next_char(),
+            KeyCode::Char('b') if alt => self.cursor = self.prev_word(),
+            KeyCode::Char('f') if alt => self.cursor = self.next_word(),
+            KeyCode::Char('w') if ctrl => self.delete_to(self.prev_word()),
+            KeyCode::Char('u') if ctrl => self.delete_to(0),
+            KeyCode::Char('k') if ctrl => self.delete_to(self.text.len()),
+            KeyCode::Char('d') if ctrl => self.delete_to(self.next_char()),
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => {
+                self.text.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+            }
+            KeyCode::Backspace if alt => self.delete_to(self.prev_word()),
+            KeyCode::Backspace => self.delete_to(self.prev_char()),
+            KeyCode::Delete => self.delete_to(self.next_char()),
+            KeyCode::Left if ctrl => self.cursor = self.prev_word(),
+            KeyCode::Right if ctrl => self.cursor = self.next_word(),
+            KeyCode::Left => self.cursor = self.prev_char(),
+            KeyCode::Right => self.cursor = self.next_char(),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Deletes the text between the cursor and `other`, leaving the cursor at the start.
+    fn delete_to(&mut self, other: usize) {
+        let (start, end) = (self.cursor.min(other), self.cursor.max(other));
+        self.text.replace_range(start..end, "");
+        self.cursor = start;
+    }
+
+    fn prev_char(&self) -> usize {
+        self.text[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_char(&self) -> usize {
+        self.text[self.cursor..]
+            .chars()
+            .next()
+            .map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    /// Start of the word before the cursor, skipping whitespace first.
+    fn prev_word(&self) -> usize {
+        let before = self.text[..self.cursor].trim_end();
+        before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8())
+    }
+
+    /// End of the word after the cursor, skipping whitespace first.
+    fn next_word(&self) -> usize {
+        let after = &self.text[self.cursor..];
+        let word_start = after.len() - after.trim_start().len();
+        after[word_start..]
+            .char_indices()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(self.text.len(), |(i, _)| self.cursor + word_start + i)
+    }
+}
@@ -0,0 +1,103 @@
+use std::{fmt, iter::Sum, ops::Add};
+
+/// Expected effort, written as `est:90m`, `est:2h`, `est:1.5h` or `est:3pts` in a description.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Estimate {
+    Minutes(i64),
+    Points(f32),
+}
+
+impl Estimate {
+    /// Parses the value after `est:`, e.g. `2h` or `3pts`.
+    pub fn parse(value: &str) -> Option<Estimate> {
+        let split = value
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(value.len());
+        let (amount, unit) = value.split_at(split);
+        let amount: f32 = amount.parse().ok().filter(|a: &f32| *a >= 0.0)?;
+        match unit.to_lowercase().as_str() {
+            "m" | "min" => Some(Estimate::Minutes(amount.round() as i64)),
+            "h" => Some(Estimate::Minutes((amount * 60.0).round() as i64)),
+            "pt" | "pts" | "p" => Some(Estimate::Points(amount)),
+            _ => None,
+        }
+    }
+
+    /// The weight of a task with this estimate when weighting the completion percentage,
+    /// counting an hour like a point.
+    pub fn weight(&self) -> f32 {
+        match self {
+            Estimate::Minutes(minutes) => *minutes as f32 / 60.0,
+            Estimate::Points(points) => *points,
+        }
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Estimate::Minutes(minutes) => f.write_str(&format_minutes(*minutes)),
+            Estimate::Points(points) => write!(f, "{}pts", points),
+        }
+    }
+}
+
+/// The sum of several estimates, keeping time and points apart.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct EstimateTotal {
+    pub minutes: i64,
+    pub points: f32,
+}
+
+impl EstimateTotal {
+    pub fn is_zero(&self) -> bool {
+        self.minutes == 0 && self.points == 0.0
+    }
+}
+
+impl Add<Estimate> for EstimateTotal {
+    type Output = EstimateTotal;
+
+    fn add(mut self, estimate: Estimate) -> EstimateTotal {
+        match estimate {
+            Estimate::Minutes(minutes) => self.minutes += minutes,
+            Estimate::Points(points) => self.points += points,
+        }
+        self
+    }
+}
+
+impl Sum<Estimate> for EstimateTotal {
+    fn sum<I: Iterator<Item = Estimate>>(estimates: I) -> EstimateTotal {
+        estimates.fold(EstimateTotal::default(), |total, estimate| total + estimate)
+    }
+}
+
+/// Written like `3h 30m`, `5pts` or `3h 30m + 5pts`; `0` when empty.
+impl fmt::Display for EstimateTotal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Guards rather than float patterns, which older compilers warn about
+        let no_points = self.points == 0.0;
+        match self.minutes {
+            0 if no_points => write!(f, "0"),
+            minutes if no_points => write!(f, "{}", Estimate::Minutes(minutes)),
+            0 => write!(f, "{}", Estimate::Points(self.points)),
+            minutes => write!(
+                f,
+                "{} + {}",
+                Estimate::Minutes(minutes),
+                Estimate::Points(self.points)
+            ),
+        }
+    }
+}
+
+/// Minutes in hours and minutes, e.g. `2h`, `45m` or `12h 30m`. Unlike durations of
+/// finished work, efforts are not broken down into days.
+fn format_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
@@ -0,0 +1,180 @@
+use crate::editor::LineEditor;
+use crate::ui::InputMode;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const MAX_ENTRIES: usize = 200;
+
+/// Previously entered task texts and filters, one list per prompt, oldest first.
+#[derive(Serialize, Deserialize, Default)]
+pub struct History {
+    #[serde(flatten)]
+    entries: HashMap<String, Vec<String>>,
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    browse: Option<Browse>,
+}
+
+/// Position while stepping through the history of one prompt.
+struct Browse {
+    prompt: &'static str,
+    /// Index into the entries; `None` while showing the draft.
+    index: Option<usize>,
+    /// What was typed before browsing started; also the Ctrl-r search term.
+    draft: String,
+}
+
+/// The history list used by a prompt, if it keeps one.
+fn prompt_name(mode: &InputMode) -> Option<&'static str> {
+    match mode {
+        InputMode::Add => Some("add"),
+        InputMode::Edit => Some("edit"),
+        InputMode::Filter => Some("filter"),
+        _ => None,
+    }
+}
+
+impl History {
+    /// Loads the history kept next to the todo file, e.g. `~/todo.history.json`.
+    pub fn load(todo_file_path: &Path) -> History {
+        let path = todo_file_path.with_file_name("todo.history.json");
+        let mut history: History = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        history.path = path;
+        history
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(&self.path, data)?;
+        Ok(())
+    }
+
+    /// Records a submitted entry as the newest one of its prompt.
+    pub fn push(&mut self, mode: &InputMode, entry: &str) {
+        self.browse = None;
+        let Some(prompt) = prompt_name(mode) else {
+            return;
+        };
+        if entry.trim().is_empty() {
+            return;
+        }
+        let entries = self.entries.entry(prompt.to_string()).or_default();
+        entries.retain(|e| e != entry);
+        entries.push(entry.to_string());
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+        let _ = self.save();
+    }
+
+    /// Stops browsing, e.g. after the input was edited.
+    pub fn reset(&mut self) {
+        self.browse = None;
+    }
+
+    /// Shows the previous entry. Returns whether the input changed.
+    pub fn older(&mut self, mode: &InputMode, input: &mut LineEditor) -> bool {
+        self.step(mode, input, |entries, index, _| match index {
+            None => entries.len().checked_sub(1),
+            Some(index) => index.checked_sub(1),
+        })
+    }
+
+    /// Shows the next entry, or the draft after the newest one.
+    pub fn newer(&mut self, mode: &InputMode, input: &mut LineEditor) -> bool {
+        let Some(Browse {
+            prompt,
+            index: Some(index),
+            ..
+        }) = &self.browse
+        else {
+            return false;
+        };
+        // Browsing another prompt's history, e.g. one left with Ctrl-c
+        if prompt_name(mode) != Some(*prompt) {
+            return false;
+        }
+        let index = *index;
+        if index + 1 < self.entries.get(*prompt).map_or(0, Vec::len) {
+            return self.step(mode, input, |_, _, _| Some(index + 1));
+        }
+        if let Some(browse) = &mut self.browse {
+            browse.index = None;
+            input.set(browse.draft.clone());
+        }
+        true
+    }
+
+    /// Shows the previous entry containing the text typed before browsing.
+    pub fn search(&mut self, mode: &InputMode, input: &mut LineEditor) -> bool {
+        self.step(mode, input, |entries, index, draft| {
+            let end = index.unwrap_or(entries.len());
+            entries[..end].iter().rposition(|e| e.contains(draft))
+        })
+    }
+
+    fn step(
+        &mut self,
+        mode: &InputMode,
+        input: &mut LineEditor,
+        next: impl Fn(&[String], Option<usize>, &str) -> Option<usize>,
+    ) -> bool {
+        let Some(prompt) = prompt_name(mode) else {
+            return false;
+        };
+        let browse = match &mut self.browse {
+            Some(browse) if browse.prompt == prompt => browse,
+            _ => self.browse.insert(Browse {
+                prompt,
+                index: None,
+                draft: input.text().to_string(),
+            }),
+        };
+        let entries = self.entries.get(prompt).map_or(&[][..], Vec::as_slice);
+        let index = next(entries, browse.index, &browse.draft);
+        match index.and_then(|index| Some((index, entries.get(index)?))) {
+            Some((index, entry)) => {
+                browse.index = Some(index);
+                input.set(entry.clone());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browsing_does_not_carry_over_to_another_prompt() {
+        let mut history = History::default();
+        for entry in ["a", "b", "c"] {
+            history.push(&InputMode::Add, entry);
+        }
+        let mut input = LineEditor::default();
+        assert!(history.older(&InputMode::Add, &mut input));
+        assert!(history.older(&InputMode::Add, &mut input));
+        assert_eq!(input.text(), "b");
+
+        // Leaving the prompt without a reset, then stepping in one without entries
+        input.clear();
+        assert!(!history.newer(&InputMode::Filter, &mut input));
+        assert!(!history.older(&InputMode::Filter, &mut input));
+        assert_eq!(input.text(), "");
+
+        assert!(history.older(&InputMode::Add, &mut input));
+        assert_eq!(input.text(), "c");
+        assert!(history.newer(&InputMode::Add, &mut input));
+        assert_eq!(input.text(), "");
+    }
+}
@@ -0,0 +1,493 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt};
+
+/// Something the user can do from the task list, bound to one or more keys.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Down,
+    Up,
+    Top,
+    Bottom,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    ScreenTop,
+    ScreenMiddle,
+    ScreenBottom,
+    MoveDown,
+    MoveUp,
+    Toggle,
+    Pending,
+    Timer,
+    Pomodoro,
+    Snooze,
+    Add,
+    Edit,
+    EditInEditor,
+    EditListInEditor,
+    EditNotes,
+    Delete,
+    RemoveDone,
+    Mark,
+    Visual,
+    SelectAll,
+    Tag,
+    Prioritize,
+    Move,
+    Undo,
+    Repeat,
+    Filter,
+    ClearFilter,
+    SaveView,
+    NextView,
+    PrevView,
+    Sort,
+    Board,
+    Agenda,
+    Stats,
+    Report,
+    ToggleDetails,
+    Backup,
+    Reset,
+    Help,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 48] = [
+        Action::Down,
+        Action::Up,
+        Action::Top,
+        Action::Bottom,
+        Action::HalfPageDown,
+        Action::HalfPageUp,
+        Action::PageDown,
+        Action::PageUp,
+        Action::ScreenTop,
+        Action::ScreenMiddle,
+        Action::ScreenBottom,
+        Action::MoveDown,
+        Action::MoveUp,
+        Action::Toggle,
+        Action::Pending,
+        Action::Timer,
+        Action::Pomodoro,
+        Action::Snooze,
+        Action::Add,
+        Action::Edit,
+        Action::EditInEditor,
+        Action::EditListInEditor,
+        Action::EditNotes,
+        Action::Delete,
+        Action::RemoveDone,
+        Action::Mark,
+        Action::Visual,
+        Action::SelectAll,
+        Action::Tag,
+        Action::Prioritize,
+        Action::Move,
+        Action::Undo,
+        Action::Repeat,
+        Action::Filter,
+        Action::ClearFilter,
+        Action::SaveView,
+        Action::NextView,
+        Action::PrevView,
+        Action::Sort,
+        Action::Board,
+        Action::Agenda,
+        Action::Stats,
+        Action::Report,
+        Action::ToggleDetails,
+        Action::Backup,
+        Action::Reset,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Down => "move down",
+            Action::Up => "move up",
+            Action::Top => "go to first task (gg), or to task N",
+            Action::Bottom => "go to last task, or to task N",
+            Action::HalfPageDown => "half page down",
+            Action::HalfPageUp => "half page up",
+            Action::PageDown => "page down",
+            Action::PageUp => "page up",
+            Action::ScreenTop => "top of screen",
+            Action::ScreenMiddle => "middle of screen",
+            Action::ScreenBottom => "bottom of screen",
+            Action::MoveDown => "move task down within its status",
+            Action::MoveUp => "move task up within its status",
+            Action::Toggle => "toggle",
+            Action::Pending => "toggle pending",
+            Action::Timer => "start/stop the timer",
+            Action::Pomodoro => "start/stop a pomodoro",
+            Action::Snooze => "snooze",
+            Action::Add => "add task",
+            Action::Edit => "edit task",
+            Action::EditInEditor => "edit task in $EDITOR",
+            Action::EditListInEditor => "edit list in $EDITOR",
+            Action::EditNotes => "edit notes in $EDITOR",
+            Action::Delete => "delete (dd)",
+            Action::RemoveDone => "remove done",
+            Action::Mark => "mark/unmark task",
+            Action::Visual => "select a range (visual mode)",
+            Action::SelectAll => "select all shown tasks",
+            Action::Tag => "tag selected tasks",
+            Action::Prioritize => "set priority of selected tasks",
+            Action::Move => "move selected tasks to a project",
+            Action::Undo => "undo last change",
+            Action::Repeat => "repeat last change",
+            Action::Filter => "filter",
+            Action::ClearFilter => "clear filter",
+            Action::SaveView => "save filter as view",
+            Action::NextView => "next view, or view N (0: all, also Alt-N)",
+            Action::PrevView => "previous view",
+            Action::Sort => "sort menu",
+            Action::Board => "board by status",
+            Action::Agenda => "agenda of the week",
+            Action::Stats => "statistics",
+            Action::Report => "report of the last week",
+            Action::ToggleDetails => "toggle detail pane",
+            Action::Backup => "backup",
+            Action::Reset => "reset",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Down => &["j"],
+            Action::Up => &["k"],
+            Action::Top => &["g"],
+            Action::Bottom => &["G"],
+            Action::HalfPageDown => &["ctrl-d"],
+            Action::HalfPageUp => &["ctrl-u"],
+            Action::PageDown => &["ctrl-f", "pagedown"],
+            Action::PageUp => &["ctrl-b", "pageup"],
+            Action::ScreenTop => &["H"],
+            Action::ScreenMiddle => &["M"],
+            Action::ScreenBottom => &["L"],
+            Action::MoveDown => &["J", "alt-j"],
+            Action::MoveUp => &["K", "alt-k"],
+            Action::Toggle => &["space"],
+            Action::Pending => &["-"],
+            Action::Timer => &["t"],
+            Action::Pomodoro => &["P"],
+            Action::Snooze => &["z"],
+            Action::Add => &["o"],
+            Action::Edit => &["i"],
+            Action::EditInEditor => &["e"],
+            Action::EditListInEditor => &["E"],
+            Action::EditNotes => &["n"],
+            Action::Delete => &["d"],
+            Action::RemoveDone => &["D"],
+            Action::Mark => &["m"],
+            Action::Visual => &["v", "V"],
+            Action::SelectAll => &["ctrl-a"],
+            Action::Tag => &["@"],
+            Action::Prioritize => &["!"],
+            Action::Move => &["+"],
+            Action::Undo => &["u"],
+            Action::Repeat => &["."],
+            Action::Filter => &["/"],
+            Action::ClearFilter => &["c"],
+            Action::SaveView => &["s"],
+            Action::NextView => &["tab"],
+            Action::PrevView => &["shift-tab"],
+            Action::Sort => &["S"],
+            Action::Board => &["B"],
+            Action::Agenda => &["A"],
+            Action::Stats => &["%"],
+            Action::Report => &["R"],
+            Action::ToggleDetails => &["p"],
+            Action::Backup => &["b"],
+            Action::Reset => &["r"],
+            Action::Help => &["?"],
+            Action::Quit => &["q"],
+        }
+    }
+}
+
+/// A key together with its modifiers, written like `ctrl-d`, `alt-j`, `space` or `D`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn from_event(key: &KeyEvent) -> KeyChord {
+        let mut modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        // Shift is already part of the character for printable keys
+        if !matches!(key.code, KeyCode::Char(_)) {
+            modifiers |= key.modifiers & KeyModifiers::SHIFT;
+        }
+        KeyChord {
+            code: key.code,
+            modifiers,
+        }
+    }
+
+    pub fn parse(spec: &str) -> Result<KeyChord, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec;
+        loop {
+            let lower = rest.to_lowercase();
+            let (modifier, len) = if lower.starts_with("ctrl-") {
+                (KeyModifiers::CONTROL, 5)
+            } else if lower.starts_with("alt-") {
+                (KeyModifiers::ALT, 4)
+            } else if lower.starts_with("shift-") {
+                (KeyModifiers::SHIFT, 6)
+            } else {
+                break;
+            };
+            modifiers |= modifier;
+            rest = &rest[len..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("Unknown key '{}'", spec)),
+                },
+            },
+        };
+        Ok(KeyChord { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) && self.code != KeyCode::BackTab {
+            f.write_str("Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::BackTab => f.write_str("Shift-Tab"),
+            KeyCode::PageUp => f.write_str("PageUp"),
+            KeyCode::PageDown => f.write_str("PageDown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// One key or a list of keys for an action in the `[keys]` table of the config.
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum KeySpec {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The active key bindings of the task list.
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyChord>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap::from_config(&HashMap::new()).expect("default key bindings are valid")
+    }
+}
+
+impl Keymap {
+    /// Builds the keymap from the defaults with the configured actions replaced, rejecting
+    /// unknown keys and keys bound to more than one action.
+    pub fn from_config(keys: &HashMap<Action, KeySpec>) -> Result<Keymap, String> {
+        let mut bindings = Vec::new();
+        for action in Action::ALL {
+            let specs: Vec<&str> = match keys.get(&action) {
+                Some(KeySpec::One(spec)) => vec![spec.as_str()],
+                Some(KeySpec::Many(specs)) => specs.iter().map(String::as_str).collect(),
+                None => action.default_keys().to_vec(),
+            };
+            let chords = specs
+                .into_iter()
+                .map(KeyChord::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            bindings.push((action, chords));
+        }
+
+        let mut seen: HashMap<KeyChord, Action> = HashMap::new();
+        for (action, chords) in &bindings {
+            for chord in chords {
+                if let KeyCode::Char('0'..='9') = chord.code {
+                    if chord.modifiers.is_empty() {
+                        return Err(format!("Key '{}' is reserved for counts", chord));
+                    }
+                    if chord.modifiers == KeyModifiers::ALT {
+                        return Err(format!("Key '{}' is reserved for views", chord));
+                    }
+                }
+                if let Some(other) = seen.insert(*chord, *action) {
+                    return Err(format!(
+                        "Key '{}' is bound to both '{}' and '{}'",
+                        chord,
+                        other.description(),
+                        action.description()
+                    ));
+                }
+            }
+        }
+
+        Ok(Keymap { bindings })
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from_event(key);
+        self.bindings
+            .iter()
+            .find(|(_, chords)| chords.contains(&chord))
+            .map(|(action, _)| *action)
+    }
+
+    /// Every binding grouped by mode, as `(keys, description)` pairs for the help overlay.
+    pub fn help_sections(&self) -> Vec<(&'static str, Vec<(String, &'static str)>)> {
+        let mut list: Vec<(String, &'static str)> = Action::ALL
+            .iter()
+            .map(|&action| (self.keys(action), action.description()))
+            .filter(|(keys, _)| !keys.is_empty())
+            .collect();
+        list.push(("0-9".to_string(), "count for the next key, e.g. 5j or 3dd"));
+        list.push(("Esc".to_string(), "clear selection"));
+
+        let fixed = |bindings: &[(&str, &'static str)]| {
+            bindings
+                .iter()
+                .map(|&(keys, description)| (keys.to_string(), description))
+                .collect()
+        };
+        vec![
+            ("Task list", list),
+            (
+                "Input prompts",
+                fixed(&[
+                    ("Enter", "confirm"),
+                    ("Esc/Ctrl-c", "cancel"),
+                    ("Left/Right", "move cursor"),
+                    ("Ctrl-Left/Right", "move by word"),
+                    ("Home/End", "start/end of line"),
+                    ("Ctrl-a/Ctrl-e", "start/end of line"),
+                    ("Backspace/Del", "delete character"),
+                    ("Ctrl-w", "delete word before cursor"),
+                    ("Ctrl-u/Ctrl-k", "delete to start/end"),
+                    ("Up/Down", "previous/next history entry"),
+                    ("Ctrl-r", "search history for the typed text"),
+                ]),
+            ),
+            (
+                "Sort menu",
+                fixed(&[
+                    (
+                        "m/c/a/d/p/l",
+                        "manual/created/alphabetical/due/priority/modified",
+                    ),
+                    ("r", "toggle descending"),
+                    ("g", "toggle grouping by status"),
+                    ("Esc/Enter/q", "close"),
+                ]),
+            ),
+            (
+                "Snooze menu",
+                fixed(&[
+                    ("h/t/w", "snooze for an hour/until tomorrow/until next week"),
+                    ("c", "snooze until a date"),
+                    ("u", "wake up"),
+                    ("s", "show snoozed tasks"),
+                    ("Esc/q", "close"),
+                ]),
+            ),
+            (
+                "Board",
+                fixed(&[
+                    ("h/l", "previous/next column"),
+                    ("j/k", "next/previous task"),
+                    ("H/L", "move task to previous/next column"),
+                    ("Esc/q", "back to the list"),
+                ]),
+            ),
+            (
+                "Agenda",
+                fixed(&[
+                    ("j/k", "next/previous day"),
+                    ("h/l", "previous/next week"),
+                    ("t", "this week"),
+                    ("Enter", "list the tasks of the day"),
+                    ("Esc/q", "back to the list"),
+                ]),
+            ),
+            ("Stats", fixed(&[("Esc/q", "back to the list")])),
+            (
+                "Report",
+                fixed(&[
+                    ("j/k", "scroll"),
+                    ("h/l", "one day shorter/longer"),
+                    ("s", "save as Markdown next to the todo file"),
+                    ("Esc/q", "back to the list"),
+                ]),
+            ),
+            (
+                "Reset dialog",
+                fixed(&[("y", "back up and reset"), ("n", "cancel")]),
+            ),
+            (
+                "Help",
+                fixed(&[("j/k/Up/Down", "scroll"), ("Esc/q/?", "close")]),
+            ),
+        ]
+    }
+
+    /// The keys of an action joined for display, e.g. `d` or `j/Down`.
+    pub fn keys(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, chords)| {
+                chords
+                    .iter()
+                    .map(KeyChord::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .unwrap_or_default()
+    }
+}
@@ -1,28 +1,51 @@
 mod app;
+mod cli;
+mod config;
+mod editor;
+mod estimate;
+mod history;
+mod keymap;
+mod pomodoro;
+mod preferences;
+mod query;
+mod report;
+mod sort;
+mod stats;
 mod task;
+mod theme;
 mod ui;
 
+use crate::ui::ui;
 use app::TodoApp;
-use chrono::Local;
+use chrono::{Datelike, Local};
+use config::{Config, SavedView};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use editor::LineEditor;
+use history::History;
+use keymap::{Action, Keymap};
+use pomodoro::{Phase, Pomodoro};
+use preferences::Preferences;
+use query::Query;
+use report::Format;
+use sort::SortKey;
 use std::{
     env,
     fs::{self, File},
-    io,
+    io::{self, Stdout, Write},
     path::{Path, PathBuf},
+    process::{self, Command},
     time::{Duration, Instant},
 };
-use tui::{
-    backend::CrosstermBackend,
-    widgets::ListState,
-    Terminal,
-};
-use ui::InputMode;
-use crate::ui::ui;
+use theme::Theme;
+use tui::{backend::CrosstermBackend, layout::Rect, widgets::ListState, Terminal};
+use ui::{Filters, InputMode, Selection};
 
 fn get_todo_file_path() -> PathBuf {
     let home_dir = env::var("HOME").expect("Unable to get $HOME directory");
@@ -36,27 +59,417 @@ fn get_todo_file_path() -> PathBuf {
     todo_file
 }
 
+/// Drops views whose query does not parse, reporting the first one in the status area.
+fn valid_views(
+    views: Vec<SavedView>,
+    status_message: &mut Option<String>,
+    message_time: &mut Option<Instant>,
+) -> Vec<SavedView> {
+    views
+        .into_iter()
+        .filter(|view| match Query::parse(&view.query) {
+            Ok(_) => true,
+            Err(err) => {
+                if status_message.is_none() {
+                    *status_message = Some(format!("View '{}': {}", view.name, err));
+                    *message_time = Some(Instant::now());
+                }
+                false
+            }
+        })
+        .collect()
+}
+
+/// The selected tasks among those matching `query`, or when nothing is selected the
+/// highlighted one and the `count - 1` tasks below it.
+fn target_ids(
+    app: &TodoApp,
+    query: &str,
+    selection: &Selection,
+    current_index: usize,
+    count: Option<usize>,
+) -> Vec<u64> {
+    let tasks = app.filter_tasks(query);
+    if selection.is_empty() {
+        tasks
+            .iter()
+            .skip(current_index)
+            .take(count.unwrap_or(1).max(1))
+            .map(|t| t.id)
+            .collect()
+    } else {
+        selection.ids(&tasks, current_index)
+    }
+}
+
+/// `1 task`, `3 tasks`.
+fn count_tasks(count: usize) -> String {
+    match count {
+        1 => "1 task".to_string(),
+        count => format!("{} tasks", count),
+    }
+}
+
+/// Tells which open dependencies keep the task at `index` from being completed.
+fn blocked_message(app: &TodoApp, index: usize) -> String {
+    let blockers: Vec<String> = app.tasks[index]
+        .blockers(&app.tasks)
+        .iter()
+        .map(|task| format!("#{} {}", task.id, task.description))
+        .collect();
+    format!("Blocked by {}.", blockers.join(", "))
+}
+
+/// Applies a change, undoing it again when it makes tasks depend on each other in a
+/// circle. A cycle that was there before does not count.
+fn reject_dependency_cycle<T>(
+    app: &mut TodoApp,
+    change: impl FnOnce(&mut TodoApp) -> T,
+) -> Result<T, String> {
+    let had_cycle = app.dependency_cycle().is_some();
+    let result = change(app);
+    match app.dependency_cycle() {
+        Some(cycle) if !had_cycle => {
+            app.undo();
+            let ids: Vec<String> = cycle.iter().map(|id| format!("#{}", id)).collect();
+            Err(format!("Dependency cycle {}.", ids.join(" -> ")))
+        }
+        _ => Ok(result),
+    }
+}
+
+/// Actions that move the highlight, see [`motion_target`].
+const MOTIONS: [Action; 11] = [
+    Action::Down,
+    Action::Up,
+    Action::Top,
+    Action::Bottom,
+    Action::HalfPageDown,
+    Action::HalfPageUp,
+    Action::PageDown,
+    Action::PageUp,
+    Action::ScreenTop,
+    Action::ScreenMiddle,
+    Action::ScreenBottom,
+];
+
+/// Actions whose key has to be pressed twice, like `gg` and `dd`.
+const DOUBLED: [Action; 2] = [Action::Top, Action::Delete];
+
+/// Changes that `.` repeats without asking for anything.
+const CHANGES: [Action; 5] = [
+    Action::Toggle,
+    Action::Pending,
+    Action::Delete,
+    Action::MoveDown,
+    Action::MoveUp,
+];
+
+/// An action of the task list with the count typed before it and, when `.` repeats a
+/// change made through a prompt, the value entered back then.
+#[derive(Clone, PartialEq, Debug)]
+struct KeyCommand {
+    action: Action,
+    count: Option<usize>,
+    value: Option<String>,
+}
+
+/// Keys typed in the task list that may only be the start of a command, like the count
+/// in `5j` or the first key of `gg` and `dd`, and the last change for `.`.
+#[derive(Default)]
+struct KeySequence {
+    count: Option<usize>,
+    first: Option<Action>,
+    last_change: Option<KeyCommand>,
+}
+
+impl KeySequence {
+    /// Feeds a key and the action bound to it, returning the command once it is complete.
+    /// The actions in `doubled` wait for their key a second time; any other key cancels
+    /// them. `Alt` with a digit switches to that view at once, like the digit and `Tab`.
+    fn feed(
+        &mut self,
+        action: Option<Action>,
+        key: &KeyEvent,
+        doubled: &[Action],
+    ) -> Option<KeyCommand> {
+        if let KeyCode::Char(c @ '0'..='9') = key.code {
+            let digit = c.to_digit(10).unwrap() as usize;
+            if key.modifiers == KeyModifiers::ALT {
+                self.count = None;
+                self.first = None;
+                return Some(KeyCommand {
+                    action: Action::NextView,
+                    count: Some(digit),
+                    value: None,
+                });
+            }
+            let modified = key
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+            if self.first.is_none() && !modified {
+                let count = self.count.unwrap_or(0).saturating_mul(10);
+                self.count = Some(count.saturating_add(digit));
+                return None;
+            }
+        }
+
+        let count = self.count.take();
+        let action = match (self.first.take(), action) {
+            (None, Some(action)) if doubled.contains(&action) => {
+                self.first = Some(action);
+                self.count = count;
+                return None;
+            }
+            (Some(first), Some(action)) if first == action => action,
+            (None, Some(action)) => action,
+            (Some(_), _) | (None, None) => return None,
+        };
+
+        if action == Action::Repeat {
+            let change = self.last_change.as_mut()?;
+            change.count = count.or(change.count);
+            return Some(change.clone());
+        }
+        let command = KeyCommand {
+            action,
+            count,
+            value: None,
+        };
+        if CHANGES.contains(&action) {
+            self.last_change = Some(command.clone());
+        }
+        Some(command)
+    }
+
+    /// Remembers a change made through a prompt so `.` can repeat it.
+    fn record(&mut self, action: Action, value: &str) {
+        self.last_change = Some(KeyCommand {
+            action,
+            count: None,
+            value: Some(value.to_string()),
+        });
+    }
+}
+
+/// Where a motion moves the highlight from `current` in a list of `len` tasks, of which
+/// `height` are shown starting at `offset`. `None` when the list is empty.
+fn motion_target(
+    action: Action,
+    count: Option<usize>,
+    current: usize,
+    len: usize,
+    offset: usize,
+    height: usize,
+) -> Option<usize> {
+    let last = len.checked_sub(1)?;
+    let n = count.unwrap_or(1).max(1);
+    let page = height.max(1);
+    let half_page = (height / 2).max(1);
+    let offset = offset.min(last);
+    let shown_last = (offset + page - 1).min(last);
+    let target = match action {
+        Action::Down => current.saturating_add(n),
+        Action::Up => current.saturating_sub(n),
+        Action::Top => count.map_or(0, |n| n.saturating_sub(1)),
+        Action::Bottom => count.map_or(last, |n| n.saturating_sub(1)),
+        Action::HalfPageDown => current.saturating_add(half_page.saturating_mul(n)),
+        Action::HalfPageUp => current.saturating_sub(half_page.saturating_mul(n)),
+        Action::PageDown => current.saturating_add(page.saturating_mul(n)),
+        Action::PageUp => current.saturating_sub(page.saturating_mul(n)),
+        Action::ScreenTop => offset.saturating_add(n - 1).min(shown_last),
+        Action::ScreenMiddle => offset + (shown_last - offset) / 2,
+        Action::ScreenBottom => shown_last.saturating_sub(n - 1).max(offset),
+        _ => current,
+    };
+    Some(target.min(last))
+}
+
+/// The first task shown once the list scrolled to keep `selected` in view, the same way
+/// the list widget scrolls.
+fn scroll_offset(offset: usize, selected: usize, len: usize, height: usize) -> usize {
+    let offset = offset.min(len.saturating_sub(1));
+    if height == 0 || (offset..offset + height).contains(&selected) {
+        offset
+    } else if selected < offset {
+        selected
+    } else {
+        selected + 1 - height
+    }
+}
+
+/// Two clicks on the same task within this time edit it.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// The task shown at a screen position in the list `area` scrolled to `offset`, and
+/// whether the position is on its `[ ]` checkbox, which follows the `> ` highlight symbol.
+fn clicked_task(
+    area: Rect,
+    offset: usize,
+    len: usize,
+    column: u16,
+    row: u16,
+) -> Option<(usize, bool)> {
+    let inside = (area.x..area.x + area.width).contains(&column)
+        && (area.y..area.y + area.height).contains(&row);
+    if !inside {
+        return None;
+    }
+    let index = offset + (row - area.y) as usize;
+    if index >= len {
+        return None;
+    }
+    let on_checkbox = (area.x + 2..area.x + 5).contains(&column);
+    Some((index, on_checkbox))
+}
+
+/// Suspends the TUI, lets the user edit `text` in `$VISUAL` or `$EDITOR` and returns the
+/// edited text once the editor exits.
+fn edit_externally(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    text: &str,
+) -> io::Result<String> {
+    let path = create_temp_file(text)?;
+
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+
+    // An empty variable counts as unset
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    // The editor may come with arguments, e.g. `code --wait`
+    let mut parts = editor.split_whitespace();
+    let status = Command::new(parts.next().unwrap_or("vi"))
+        .args(parts)
+        .arg(&path)
+        .status();
+
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    terminal.clear()?;
+
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    let status = status?;
+    if !status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("{} exited with {}", editor, status),
+        ));
+    }
+    edited
+}
+
+/// Writes `text` to a new file in the temp directory. The file must not exist yet, so a
+/// file or symlink someone else put there is never written through.
+fn create_temp_file(text: &str) -> io::Result<PathBuf> {
+    let mut attempt = 0;
+    loop {
+        let path = env::temp_dir().join(format!("todo-{}-{}.txt", process::id(), attempt));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                file.write_all(text.as_bytes())?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 fn main() -> Result<(), io::Error> {
     let todo_file_path = get_todo_file_path();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(err) = cli::run(&args, &todo_file_path) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
     let mut app = TodoApp::load_from_file(&todo_file_path).unwrap_or_else(|_| TodoApp::new());
     app.reorder_tasks();
 
+    let mut status_message: Option<String> = None; // Temporary status message
+    let mut message_time: Option<Instant> = None; // Time when message is shown
+
+    let config_file_path = config::get_config_file_path();
+    let config = Config::load_from_file(&config_file_path).unwrap_or_else(|err| {
+        status_message = Some(err);
+        message_time = Some(Instant::now());
+        Config::default()
+    });
+    app.weight_by_estimate = config.weight_by_estimate;
+    let keymap = Keymap::from_config(&config.keys).unwrap_or_else(|err| {
+        status_message = Some(format!("{}; using default key bindings", err));
+        message_time = Some(Instant::now());
+        Keymap::default()
+    });
+    let theme_name = config
+        .theme
+        .clone()
+        .unwrap_or_else(theme::default_theme_name);
+    let theme = Theme::load(&theme_name, &config.themes).unwrap_or_else(|err| {
+        status_message = Some(err);
+        message_time = Some(Instant::now());
+        Theme::default()
+    });
+    let mut filters = Filters {
+        views: valid_views(config.views, &mut status_message, &mut message_time),
+        active_view: None,
+        filter: String::new(),
+    };
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     let mut current_index = 0;
-    let mut filter = String::new();
-    let mut input = String::new();
+    let mut input = LineEditor::default();
+    let mut history = History::load(&todo_file_path);
+    let mut preferences = Preferences::load(&todo_file_path);
+    app.set_sort(preferences.sort(filters.list_name()));
+    let mut selection = Selection::default();
     let mut input_mode = InputMode::View;
-    let mut status_message: Option<String> = None; // Temporary status message
-    let mut message_time: Option<Instant> = None; // Time when message is shown
     let mut reset_dialog = false;
     let mut list_state = ListState::default();
     list_state.select(Some(current_index));
+    // Mirrors the scroll position of the list, which `ListState` keeps to itself
+    let mut list_offset = 0;
+    let mut list_area = Rect::default();
+    let mut overlay_height = 0;
+    let mut key_sequence = KeySequence::default();
+    let mut last_click: Option<(Instant, usize)> = None;
+    let mut pomodoro: Option<Pomodoro> = None;
 
     loop {
         // Check if the status message should be cleared after 3 seconds
@@ -66,32 +479,190 @@ fn main() -> Result<(), io::Error> {
                 message_time = None; // Reset the timer
             }
         }
+        // Snoozed tasks come back on their own
+        let woken = app.wake_up(Local::now());
+        if !woken.is_empty() {
+            let _ = app.save_to_file(&todo_file_path);
+            status_message = Some(format!("Back from snooze: {}.", woken.join(", ")));
+            message_time = Some(Instant::now());
+        }
+        if let Some(ended) = pomodoro.as_mut().and_then(Pomodoro::tick) {
+            let pomodoro = pomodoro.as_ref().unwrap();
+            if ended == Phase::Work {
+                app.add_pomodoro(pomodoro.task_id);
+                let _ = app.save_to_file(&todo_file_path);
+            }
+            status_message = Some(match ended {
+                Phase::Work => format!("Pomodoro {} done, take a break.", pomodoro.finished),
+                Phase::Break => "Break over, back to work.".to_string(),
+            });
+            message_time = Some(Instant::now());
+            // Ring the terminal bell
+            let _ = io::stdout().write_all(b"\x07");
+            let _ = io::stdout().flush();
+        }
+        let query = match input_mode {
+            InputMode::Filter => filters.query_with(input.text()),
+            _ => filters.query(),
+        };
         terminal.draw(|f| {
-            ui(
+            (list_area, overlay_height) = ui(
                 f,
                 &app,
                 &mut list_state,
-                &filter,
+                &filters,
+                &selection,
                 &input,
                 &input_mode,
                 &status_message,
+                &keymap,
+                &theme,
+                &preferences,
+                pomodoro.as_ref(),
             );
         })?;
+        list_offset = scroll_offset(
+            list_offset,
+            current_index,
+            app.filter_tasks(&query).len(),
+            list_area.height as usize,
+        );
 
         if event::poll(Duration::from_millis(200))? {
-            if let Event::Key(key) = event::read()? {
-                match (key.code, &input_mode) {
-                    (KeyCode::Char('q'), InputMode::View) => {
-                        // Clear the terminal and exit
-                        execute!(
-                            terminal.backend_mut(),
-                            LeaveAlternateScreen,
-                            DisableMouseCapture
-                        )?;
-                        terminal.show_cursor()?;
-                        return Ok(());
-                    }
-                    (KeyCode::Char('b'), InputMode::View) => {
+            let event = event::read()?;
+            if let Event::Paste(text) = &event {
+                match input_mode {
+                    InputMode::View
+                    | InputMode::Sort
+                    | InputMode::Snooze
+                    | InputMode::Board { .. }
+                    | InputMode::Agenda { .. }
+                    | InputMode::Stats
+                    | InputMode::Report { .. }
+                    | InputMode::Help(_) => {}
+                    _ => input.insert_str(text),
+                }
+            }
+            if let Event::Mouse(mouse) = event {
+                let tasks_filtered = app.filter_tasks(&query);
+                match (mouse.kind, &input_mode) {
+                    (MouseEventKind::ScrollDown, InputMode::Help(scroll)) => {
+                        let max_scroll =
+                            ui::max_scroll(ui::help_lines(&keymap).len(), overlay_height);
+                        input_mode = InputMode::Help((scroll + 1).min(max_scroll));
+                    }
+                    (MouseEventKind::ScrollUp, InputMode::Help(scroll)) => {
+                        input_mode = InputMode::Help(scroll.saturating_sub(1));
+                    }
+                    (MouseEventKind::ScrollDown, InputMode::View)
+                        if current_index + 1 < tasks_filtered.len() =>
+                    {
+                        current_index += 1;
+                        list_state.select(Some(current_index));
+                    }
+                    (MouseEventKind::ScrollUp, InputMode::View) => {
+                        current_index = current_index.saturating_sub(1);
+                        list_state.select(Some(current_index));
+                    }
+                    (MouseEventKind::Down(MouseButton::Left), InputMode::View) => {
+                        if let Some((index, on_checkbox)) = clicked_task(
+                            list_area,
+                            list_offset,
+                            tasks_filtered.len(),
+                            mouse.column,
+                            mouse.row,
+                        ) {
+                            let double_click = last_click.is_some_and(|(time, row)| {
+                                row == index && time.elapsed() < DOUBLE_CLICK
+                            });
+                            current_index = index;
+                            list_state.select(Some(current_index));
+                            let task = &tasks_filtered[index];
+                            last_click = None;
+                            if on_checkbox {
+                                let original_index =
+                                    app.tasks.iter().position(|t| t.id == task.id).unwrap();
+                                if app.toggle_task(original_index) {
+                                    let _ = app.save_to_file(&todo_file_path);
+                                } else {
+                                    status_message = Some(blocked_message(&app, original_index));
+                                    message_time = Some(Instant::now());
+                                }
+                            } else if double_click {
+                                input_mode = InputMode::Edit;
+                                input.set(task.description.clone());
+                            } else {
+                                last_click = Some((Instant::now(), index));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if let Event::Key(key) = event {
+                if reset_dialog {
+                    match key.code {
+                        KeyCode::Char('y') => {
+                            let current_date = Local::now().format("%Y-%m-%d").to_string();
+                            let backup_file_name = format!("todo.{}.json", current_date);
+                            let backup_file_path = todo_file_path.with_file_name(backup_file_name);
+                            if fs::copy(&todo_file_path, backup_file_path).is_ok() {
+                                fs::write(&todo_file_path, "[]")
+                                    .expect("Unable to clear todo file");
+                                status_message =
+                                    Some("Backup created and todo list reset.".to_string());
+                            } else {
+                                status_message = Some("Backup failed. Reset canceled.".to_string());
+                            }
+
+                            message_time = Some(Instant::now());
+                            reset_dialog = false;
+                        }
+                        KeyCode::Char('n') | KeyCode::Esc => {
+                            // Cancel the reset process
+                            status_message = Some("Reset canceled.".to_string());
+                            message_time = Some(Instant::now());
+                            reset_dialog = false;
+                        }
+                        _ => {}
+                    }
+                    // The dialog takes every key, so `n` does not also edit notes
+                    continue;
+                }
+                let command = match input_mode {
+                    InputMode::View => {
+                        // With a selection, `d` deletes it right away
+                        let doubled: &[Action] = if selection.is_empty() {
+                            &DOUBLED
+                        } else {
+                            &[Action::Top]
+                        };
+                        key_sequence.feed(keymap.action(&key), &key, doubled)
+                    }
+                    _ => None,
+                };
+                let count = command.as_ref().and_then(|c| c.count);
+                let mut action = command.as_ref().map(|c| c.action);
+                let mut code = key.code;
+                // `.` repeats a change made through a prompt by entering its value again
+                if let Some(value) = command.and_then(|c| c.value) {
+                    input_mode = match action {
+                        Some(Action::Tag) => InputMode::Tag,
+                        Some(Action::Prioritize) => InputMode::Prioritize,
+                        _ => InputMode::Move,
+                    };
+                    input.set(value);
+                    action = None;
+                    code = KeyCode::Enter;
+                }
+                match (action, code, &input_mode) {
+                    (None, KeyCode::Char('c'), _) if key.modifiers == KeyModifiers::CONTROL => {
+                        input_mode = InputMode::View;
+                        history.reset();
+                        input.clear();
+                    }
+                    (Some(Action::Quit), _, _) => break,
+                    (Some(Action::Backup), _, _) => {
                         let current_date = Local::now().format("%Y-%m-%d").to_string();
                         let backup_file_name = format!("todo.{}.json", current_date);
                         let backup_file_path = todo_file_path.with_file_name(backup_file_name);
@@ -103,63 +674,163 @@ fn main() -> Result<(), io::Error> {
                         }
                         message_time = Some(Instant::now()); // Start the 3-second timer
                     }
-                    (KeyCode::Char('r'), InputMode::View) => {
+                    (Some(Action::Reset), _, _) => {
                         reset_dialog = true;
                         status_message =
                             Some("Press 'y' to confirm reset, 'n' to cancel.".to_string());
                         message_time = Some(Instant::now()); // Show status message
                     }
-                    (KeyCode::Char('j'), InputMode::View) => {
-                        let tasks_filtered_len = app.filter_tasks(&filter).len();
-                        if current_index + 1 < tasks_filtered_len {
-                            current_index += 1;
+                    (Some(action), _, _) if MOTIONS.contains(&action) => {
+                        let tasks_filtered_len = app.filter_tasks(&query).len();
+                        let height = list_area.height as usize;
+                        if let Some(target) = motion_target(
+                            action,
+                            count,
+                            current_index,
+                            tasks_filtered_len,
+                            list_offset,
+                            height,
+                        ) {
+                            current_index = target;
                             list_state.select(Some(current_index));
                         }
                     }
-                    (KeyCode::Char('k'), InputMode::View) => {
-                        if current_index > 0 {
-                            current_index -= 1;
-                            list_state.select(Some(current_index));
+                    (Some(action @ (Action::MoveDown | Action::MoveUp)), _, _) => {
+                        if app.sort.key != SortKey::Manual {
+                            status_message =
+                                Some("Switch to manual sort to move tasks.".to_string());
+                            message_time = Some(Instant::now());
+                        } else {
+                            let mut moved = false;
+                            for _ in 0..count.unwrap_or(1) {
+                                let tasks_filtered = app.filter_tasks(&query);
+                                let neighbor_index = if action == Action::MoveDown {
+                                    current_index.checked_add(1)
+                                } else {
+                                    current_index.checked_sub(1)
+                                };
+                                let task = tasks_filtered.get(current_index);
+                                let neighbor = neighbor_index.and_then(|i| tasks_filtered.get(i));
+                                let (Some(task), Some(neighbor)) = (task, neighbor) else {
+                                    break;
+                                };
+                                if !app.swap_tasks(task.id, neighbor.id) {
+                                    break;
+                                }
+                                moved = true;
+                                // Keep the moved task highlighted
+                                current_index = app
+                                    .filter_tasks(&query)
+                                    .iter()
+                                    .position(|t| t.id == task.id)
+                                    .unwrap_or(current_index);
+                            }
+                            if moved {
+                                let _ = app.save_to_file(&todo_file_path);
+                                list_state.select(Some(current_index));
+                            }
                         }
                     }
-                    (KeyCode::Char(' '), InputMode::View) => {
-                        let tasks_filtered = app.filter_tasks(&filter);
+                    (Some(action @ (Action::Toggle | Action::Pending | Action::Delete)), _, _)
+                        if !selection.is_empty() || count.is_some_and(|n| n > 1) =>
+                    {
+                        let ids = target_ids(&app, &query, &selection, current_index, count);
+                        let mut blocked = 0;
+                        let verb = match action {
+                            Action::Toggle => {
+                                blocked = app.toggle_tasks(&ids);
+                                "Toggled"
+                            }
+                            Action::Pending => {
+                                app.toggle_pending_tasks(&ids);
+                                "Toggled pending on"
+                            }
+                            _ => {
+                                app.delete_tasks(&ids);
+                                "Deleted"
+                            }
+                        };
+                        let _ = app.save_to_file(&todo_file_path);
+                        status_message = Some(match blocked {
+                            0 => format!("{} {}.", verb, count_tasks(ids.len())),
+                            blocked => format!(
+                                "{} {}, {} blocked.",
+                                verb,
+                                count_tasks(ids.len() - blocked),
+                                blocked
+                            ),
+                        });
+                        message_time = Some(Instant::now());
+                        selection.clear();
+                        let tasks_filtered_len = app.filter_tasks(&query).len();
+                        current_index = current_index.min(tasks_filtered_len.saturating_sub(1));
+                        list_state.select(Some(current_index));
+                    }
+                    (Some(Action::Toggle), _, _) => {
+                        let tasks_filtered = app.filter_tasks(&query);
                         if let Some(task) = tasks_filtered.get(current_index) {
-                            let original_index = app
-                                .tasks
-                                .iter()
-                                .position(|t| t.description == task.description)
-                                .unwrap();
-                            app.toggle_task(original_index);
-                            app.save_to_file(&todo_file_path);
+                            let original_index =
+                                app.tasks.iter().position(|t| t.id == task.id).unwrap();
+                            if app.toggle_task(original_index) {
+                                let _ = app.save_to_file(&todo_file_path);
+                            } else {
+                                status_message = Some(blocked_message(&app, original_index));
+                                message_time = Some(Instant::now());
+                            }
                         }
                     }
-                    (KeyCode::Char('-'), InputMode::View) => {
-                        let tasks_filtered = app.filter_tasks(&filter);
+                    (Some(Action::Pending), _, _) => {
+                        let tasks_filtered = app.filter_tasks(&query);
                         if let Some(task) = tasks_filtered.get(current_index) {
-                            let original_index = app
-                                .tasks
-                                .iter()
-                                .position(|t| t.description == task.description)
-                                .unwrap();
+                            let original_index =
+                                app.tasks.iter().position(|t| t.id == task.id).unwrap();
                             app.toggle_pending(original_index);
                             let _ = app.save_to_file(&todo_file_path);
                         }
                     }
-                    (KeyCode::Char('o'), InputMode::View) => {
+                    (Some(Action::Timer), _, _) => {
+                        let tasks_filtered = app.filter_tasks(&query);
+                        if let Some(task) = tasks_filtered.get(current_index) {
+                            let index = app.tasks.iter().position(|t| t.id == task.id).unwrap();
+                            status_message = Some(match task.timer_started() {
+                                Some(started) => format!(
+                                    "Timer stopped after {}.",
+                                    stats::format_duration(Local::now() - started)
+                                ),
+                                None => format!("Timer started on '{}'.", task.description),
+                            });
+                            app.toggle_timer(index);
+                            message_time = Some(Instant::now());
+                            let _ = app.save_to_file(&todo_file_path);
+                        }
+                    }
+                    (Some(Action::Pomodoro), _, _) => {
+                        let tasks_filtered = app.filter_tasks(&query);
+                        if let Some(task) = tasks_filtered.get(current_index) {
+                            // Pressed on the task it runs on, it stops; on another task it
+                            // starts over there
+                            if pomodoro.as_ref().is_some_and(|p| p.task_id == task.id) {
+                                pomodoro = None;
+                                status_message = Some("Pomodoro stopped.".to_string());
+                            } else {
+                                pomodoro = Some(Pomodoro::start(task.id, config.pomodoro));
+                                status_message =
+                                    Some(format!("Pomodoro started on '{}'.", task.description));
+                            }
+                            message_time = Some(Instant::now());
+                        }
+                    }
+                    (Some(Action::Add), _, _) => {
                         input_mode = InputMode::Add;
                         input.clear();
                     }
-                    (KeyCode::Char('d'), InputMode::View) => {
-                        let tasks_filtered = app.filter_tasks(&filter);
+                    (Some(Action::Delete), _, _) => {
+                        let tasks_filtered = app.filter_tasks(&query);
                         if let Some(task) = tasks_filtered.get(current_index) {
-                            let original_index = app
-                                .tasks
-                                .iter()
-                                .position(|t| t.description == task.description)
-                                .unwrap();
+                            let original_index =
+                                app.tasks.iter().position(|t| t.id == task.id).unwrap();
                             app.delete_task(original_index);
-                            let _ = app.save_to_file(&todo_file_path).unwrap();
+                            app.save_to_file(&todo_file_path).unwrap();
                             status_message = Some("Task deleted.".to_string());
                             message_time = Some(Instant::now());
                             if current_index >= tasks_filtered.len() - 1 && current_index > 0 {
@@ -168,105 +839,629 @@ fn main() -> Result<(), io::Error> {
                             list_state.select(Some(current_index));
                         }
                     }
-                    (KeyCode::Char('D'), InputMode::View) => {
+                    (Some(Action::Mark), _, _) => {
+                        let tasks_filtered = app.filter_tasks(&query);
+                        if let Some(task) = tasks_filtered.get(current_index) {
+                            if !selection.marked.remove(&task.id) {
+                                selection.marked.insert(task.id);
+                            }
+                            if current_index + 1 < tasks_filtered.len() {
+                                current_index += 1;
+                                list_state.select(Some(current_index));
+                            }
+                        }
+                    }
+                    (Some(Action::Visual), _, _) => {
+                        selection.anchor = match selection.anchor {
+                            Some(_) => None,
+                            None => Some(current_index),
+                        };
+                    }
+                    (Some(Action::SelectAll), _, _) => {
+                        let ids: Vec<u64> = app.filter_tasks(&query).iter().map(|t| t.id).collect();
+                        selection.anchor = None;
+                        if ids.iter().all(|id| selection.marked.contains(id)) {
+                            selection.marked.clear();
+                        } else {
+                            selection.marked.extend(ids);
+                        }
+                    }
+                    (Some(action @ (Action::Tag | Action::Prioritize | Action::Move)), _, _)
+                        if !target_ids(&app, &query, &selection, current_index, None)
+                            .is_empty() =>
+                    {
+                        input_mode = match action {
+                            Action::Tag => InputMode::Tag,
+                            Action::Prioritize => InputMode::Prioritize,
+                            _ => InputMode::Move,
+                        };
+                        input.clear();
+                    }
+                    (Some(Action::Undo), _, _) => {
+                        if app.undo() {
+                            let _ = app.save_to_file(&todo_file_path);
+                            status_message = Some("Undid the last change.".to_string());
+                            let tasks_filtered_len = app.filter_tasks(&query).len();
+                            current_index = current_index.min(tasks_filtered_len.saturating_sub(1));
+                            list_state.select(Some(current_index));
+                        } else {
+                            status_message = Some("Nothing to undo.".to_string());
+                        }
+                        message_time = Some(Instant::now());
+                    }
+                    (Some(Action::RemoveDone), _, _) => {
                         app.remove_done_tasks();
-                        let _ = app.save_to_file(&todo_file_path).unwrap();
+                        app.save_to_file(&todo_file_path).unwrap();
                         status_message = Some("Completed tasks removed.".to_string());
                         message_time = Some(Instant::now());
                         current_index = 0;
                         list_state.select(Some(current_index));
                     }
-                    (KeyCode::Char('/'), InputMode::View) => {
+                    (Some(Action::Filter), _, _) => {
+                        // Start from the current filter so it can be refined
                         input_mode = InputMode::Filter;
-                        input.clear();
+                        input.set(filters.filter.clone());
+                    }
+                    (Some(Action::Help), _, _) => {
+                        input_mode = InputMode::Help(0);
+                    }
+                    (_, KeyCode::Char('j') | KeyCode::Down, InputMode::Help(scroll)) => {
+                        let max_scroll =
+                            ui::max_scroll(ui::help_lines(&keymap).len(), overlay_height);
+                        input_mode = InputMode::Help((scroll + 1).min(max_scroll));
+                    }
+                    (_, KeyCode::Char('k') | KeyCode::Up, InputMode::Help(scroll)) => {
+                        input_mode = InputMode::Help(scroll.saturating_sub(1));
+                    }
+                    (_, KeyCode::Char('q' | '?'), InputMode::Help(_)) => {
+                        input_mode = InputMode::View;
+                    }
+                    (Some(action @ (Action::NextView | Action::PrevView)), _, _)
+                        if !filters.views.is_empty() =>
+                    {
+                        // Tab 0 lists all tasks, the saved views follow
+                        let tabs = filters.views.len() + 1;
+                        let tab = filters.active_view.map_or(0, |i| i + 1);
+                        let tab = match (action, count) {
+                            (Action::NextView, Some(n)) => n.min(tabs - 1),
+                            (Action::NextView, None) => (tab + 1) % tabs,
+                            _ => (tab + tabs - 1) % tabs,
+                        };
+                        filters.active_view = tab.checked_sub(1);
+                        selection.anchor = None;
+                        app.set_sort(preferences.sort(filters.list_name()));
+                        current_index = 0;
+                        list_state.select(Some(current_index));
+                    }
+                    (Some(Action::Sort), _, _) => {
+                        input_mode = InputMode::Sort;
+                    }
+                    (_, KeyCode::Char(c), InputMode::Sort) => {
+                        let mut sort = app.sort;
+                        match c {
+                            'r' => sort.descending = !sort.descending,
+                            'g' => sort.group_by_status = !sort.group_by_status,
+                            'q' | 'S' => input_mode = InputMode::View,
+                            c => {
+                                if let Some(key) = SortKey::from_key(c) {
+                                    sort.key = key;
+                                }
+                            }
+                        }
+                        if sort != app.sort {
+                            // Keep the highlighted task highlighted in its new place
+                            let current_id =
+                                app.filter_tasks(&query).get(current_index).map(|t| t.id);
+                            app.set_sort(sort);
+                            preferences
+                                .sorts
+                                .insert(filters.list_name().to_string(), sort);
+                            let _ = preferences.save();
+                            current_index = app
+                                .filter_tasks(&query)
+                                .iter()
+                                .position(|t| Some(t.id) == current_id)
+                                .unwrap_or(0);
+                            list_state.select(Some(current_index));
+                        }
+                    }
+                    (_, KeyCode::Enter, InputMode::Sort) => {
+                        input_mode = InputMode::View;
+                    }
+                    (Some(Action::Snooze), _, _) => {
+                        input_mode = InputMode::Snooze;
+                    }
+                    (_, KeyCode::Char(c), InputMode::Snooze) => {
+                        let ids = target_ids(&app, &query, &selection, current_index, None);
+                        let choice = ui::snooze_choices(Local::now())
+                            .into_iter()
+                            .find(|(key, _, _)| *key == c);
+                        let message = match (c, choice) {
+                            (_, Some((_, name, until))) if !ids.is_empty() => {
+                                app.snooze_tasks(&ids, Some(until));
+                                Some(format!("Snoozed {} {}.", count_tasks(ids.len()), name))
+                            }
+                            ('u', _) if !ids.is_empty() => {
+                                app.snooze_tasks(&ids, None);
+                                Some(format!("Woke up {}.", count_tasks(ids.len())))
+                            }
+                            ('c', _) if !ids.is_empty() => {
+                                input_mode = InputMode::SnoozeUntil;
+                                input.clear();
+                                None
+                            }
+                            ('s', _) => {
+                                filters.filter = "is:snoozed".to_string();
+                                input_mode = InputMode::View;
+                                None
+                            }
+                            ('q' | 'z', _) => {
+                                input_mode = InputMode::View;
+                                None
+                            }
+                            _ => None,
+                        };
+                        if let Some(message) = message {
+                            let _ = app.save_to_file(&todo_file_path);
+                            status_message = Some(message);
+                            message_time = Some(Instant::now());
+                            selection.clear();
+                            input_mode = InputMode::View;
+                        }
+                        if let InputMode::View = input_mode {
+                            let tasks_filtered_len = app.filter_tasks(&filters.query()).len();
+                            current_index = current_index.min(tasks_filtered_len.saturating_sub(1));
+                            list_state.select(Some(current_index));
+                        }
+                    }
+                    (_, KeyCode::Enter, InputMode::SnoozeUntil) => {
+                        match query::parse_date(input.text().trim(), true) {
+                            Ok(until) => {
+                                let ids = target_ids(&app, &query, &selection, current_index, None);
+                                app.snooze_tasks(&ids, Some(until));
+                                let _ = app.save_to_file(&todo_file_path);
+                                status_message = Some(format!(
+                                    "Snoozed {} until {}.",
+                                    count_tasks(ids.len()),
+                                    until.format("%Y-%m-%d %H:%M")
+                                ));
+                                selection.clear();
+                                input_mode = InputMode::View;
+                                input.clear();
+                                let tasks_filtered_len = app.filter_tasks(&query).len();
+                                current_index =
+                                    current_index.min(tasks_filtered_len.saturating_sub(1));
+                                list_state.select(Some(current_index));
+                            }
+                            // Keep the prompt open so the date can be fixed
+                            Err(err) => status_message = Some(err.to_string()),
+                        }
+                        message_time = Some(Instant::now());
+                    }
+                    (Some(Action::Board), _, _) => {
+                        // Start on the highlighted task
+                        let task = app.filter_tasks(&query).get(current_index).cloned();
+                        let column = task
+                            .as_ref()
+                            .and_then(|t| ui::BOARD_COLUMNS.iter().position(|s| *s == t.status))
+                            .unwrap_or(0);
+                        let row = task
+                            .and_then(|task| {
+                                ui::board_column(&app, &query, column)
+                                    .iter()
+                                    .position(|t| t.id == task.id)
+                            })
+                            .unwrap_or(0);
+                        input_mode = InputMode::Board { column, row };
+                    }
+                    (_, KeyCode::Char('q'), InputMode::Board { .. }) => {
+                        input_mode = InputMode::View;
+                    }
+                    (_, KeyCode::Char(c), InputMode::Board { column, row }) => {
+                        let (mut column, mut row) = (*column, *row);
+                        match c {
+                            'h' => column = column.saturating_sub(1),
+                            'l' => column = (column + 1).min(ui::BOARD_COLUMNS.len() - 1),
+                            'j' => row += 1,
+                            'k' => row = row.saturating_sub(1),
+                            'H' | 'L' => {
+                                let target = if c == 'H' {
+                                    column.checked_sub(1)
+                                } else {
+                                    Some(column + 1).filter(|&c| c < ui::BOARD_COLUMNS.len())
+                                };
+                                let tasks = ui::board_column(&app, &query, column);
+                                if let (Some(target), Some(task)) = (target, tasks.get(row)) {
+                                    let original_index =
+                                        app.tasks.iter().position(|t| t.id == task.id).unwrap();
+                                    let status = ui::BOARD_COLUMNS[target].clone();
+                                    if app.set_status(original_index, status) {
+                                        let _ = app.save_to_file(&todo_file_path);
+                                        // Follow the task to its new column
+                                        column = target;
+                                        row = ui::board_column(&app, &query, column)
+                                            .iter()
+                                            .position(|t| t.id == task.id)
+                                            .unwrap_or(0);
+                                    } else {
+                                        status_message =
+                                            Some(blocked_message(&app, original_index));
+                                        message_time = Some(Instant::now());
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                        let len = ui::board_column(&app, &query, column).len();
+                        row = row.min(len.saturating_sub(1));
+                        input_mode = InputMode::Board { column, row };
+                    }
+                    (Some(Action::Stats), _, _) => {
+                        input_mode = InputMode::Stats;
+                    }
+                    (_, KeyCode::Char('q'), InputMode::Stats) => {
+                        input_mode = InputMode::View;
                     }
-                    (KeyCode::Char('i'), InputMode::View) => {
+                    (Some(Action::Report), _, _) => {
+                        input_mode = InputMode::Report { days: 7, scroll: 0 };
+                    }
+                    (_, KeyCode::Char('q'), InputMode::Report { .. }) => {
+                        input_mode = InputMode::View;
+                    }
+                    (_, KeyCode::Char('s'), InputMode::Report { days, .. }) => {
+                        // Named like backups, next to the todo file
+                        let report = app.report(ui::report_since(*days));
+                        let file_name =
+                            format!("todo.report.{}.md", Local::now().format("%Y-%m-%d"));
+                        let report_file_path = todo_file_path.with_file_name(file_name);
+                        status_message = Some(
+                            match fs::write(&report_file_path, report.render(Format::Markdown)) {
+                                Ok(()) => format!("Report saved to {}", report_file_path.display()),
+                                Err(err) => format!("Saving the report failed: {}", err),
+                            },
+                        );
+                        message_time = Some(Instant::now());
+                    }
+                    (_, KeyCode::Char(c), InputMode::Report { days, scroll }) => {
+                        let (mut days, mut scroll) = (*days, *scroll);
+                        match c {
+                            'j' => scroll = scroll.saturating_add(1),
+                            'k' => scroll = scroll.saturating_sub(1),
+                            'h' => days = (days - 1).max(1),
+                            'l' => days += 1,
+                            _ => {}
+                        }
+                        // Stop where the last line of the report reaches the bottom
+                        let text = app.report(ui::report_since(days)).render(Format::Text);
+                        scroll = scroll.min(ui::max_scroll(text.lines().count(), overlay_height));
+                        input_mode = InputMode::Report { days, scroll };
+                    }
+                    (Some(Action::Agenda), _, _) => {
+                        let weekday = Local::now().date_naive().weekday();
+                        input_mode = InputMode::Agenda {
+                            week: 0,
+                            day: weekday.num_days_from_monday() as usize,
+                        };
+                    }
+                    (_, KeyCode::Char('q'), InputMode::Agenda { .. }) => {
+                        input_mode = InputMode::View;
+                    }
+                    (_, KeyCode::Char(c), InputMode::Agenda { week, day }) => {
+                        let (mut week, mut day) = (*week, *day);
+                        match c {
+                            'j' => day = (day + 1).min(6),
+                            'k' => day = day.saturating_sub(1),
+                            'h' => week -= 1,
+                            'l' => week += 1,
+                            't' => {
+                                week = 0;
+                                let weekday = Local::now().date_naive().weekday();
+                                day = weekday.num_days_from_monday() as usize;
+                            }
+                            _ => {}
+                        }
+                        input_mode = InputMode::Agenda { week, day };
+                    }
+                    (_, KeyCode::Enter, InputMode::Agenda { week, day }) => {
+                        // Same tasks as on the agenda: due that day, or created that day
+                        // when without a due date
+                        let date = ui::agenda_monday(*week) + chrono::Duration::days(*day as i64);
+                        filters.filter = format!(
+                            "due:{0} or (due:none and created:{0})",
+                            date.format("%Y-%m-%d")
+                        );
+                        input_mode = InputMode::View;
+                        selection.anchor = None;
+                        current_index = 0;
+                        list_state.select(Some(current_index));
+                    }
+                    (Some(Action::SaveView), _, _) => {
+                        if filters.filter.is_empty() {
+                            status_message =
+                                Some("Filter with '/' before saving a view.".to_string());
+                            message_time = Some(Instant::now());
+                        } else {
+                            input_mode = InputMode::SaveView;
+                            input.clear();
+                        }
+                    }
+                    (Some(Action::ClearFilter), _, _) if !filters.filter.is_empty() => {
+                        filters.filter.clear();
+                        current_index = 0;
+                        list_state.select(Some(current_index));
+                    }
+                    (Some(action @ (Action::EditInEditor | Action::EditListInEditor)), _, _) => {
+                        let ids: Vec<u64> = if action == Action::EditListInEditor {
+                            app.tasks.iter().map(|t| t.id).collect()
+                        } else {
+                            app.filter_tasks(&query)
+                                .get(current_index)
+                                .map(|t| t.id)
+                                .into_iter()
+                                .collect()
+                        };
+                        if !ids.is_empty() {
+                            match edit_externally(&mut terminal, &app.to_text(&ids)) {
+                                Ok(text) => {
+                                    status_message = Some(
+                                        match reject_dependency_cycle(&mut app, |app| {
+                                            app.apply_text(&text, &ids)
+                                        }) {
+                                            Ok(summary) => {
+                                                let _ = app.save_to_file(&todo_file_path);
+                                                format!("Edited: {}.", summary)
+                                            }
+                                            Err(err) => format!("{} Changes discarded.", err),
+                                        },
+                                    );
+                                }
+                                Err(err) => {
+                                    status_message = Some(format!("Editor failed: {}", err));
+                                }
+                            }
+                            message_time = Some(Instant::now());
+                            let tasks_filtered_len = app.filter_tasks(&query).len();
+                            current_index = current_index.min(tasks_filtered_len.saturating_sub(1));
+                            list_state.select(Some(current_index));
+                        }
+                    }
+                    (Some(Action::EditNotes), _, _) => {
+                        let tasks_filtered = app.filter_tasks(&query);
+                        if let Some(task) = tasks_filtered.get(current_index) {
+                            let original_index =
+                                app.tasks.iter().position(|t| t.id == task.id).unwrap();
+                            match edit_externally(&mut terminal, &task.notes) {
+                                Ok(notes) => {
+                                    app.set_notes(original_index, notes);
+                                    let _ = app.save_to_file(&todo_file_path);
+                                    status_message = Some("Notes saved.".to_string());
+                                }
+                                Err(err) => {
+                                    status_message = Some(format!("Editor failed: {}", err));
+                                }
+                            }
+                            message_time = Some(Instant::now());
+                        }
+                    }
+                    (Some(Action::ToggleDetails), _, _) => {
+                        preferences.show_details = !preferences.show_details;
+                        let _ = preferences.save();
+                    }
+                    (Some(Action::Edit), _, _) => {
                         input_mode = InputMode::Edit;
                         input.clear();
-                        if let Some(task) = app.filter_tasks(&filter).get(current_index) {
-                            input = task.description.clone();
+                        if let Some(task) = app.filter_tasks(&query).get(current_index) {
+                            input.set(task.description.clone());
                         }
                     }
-                    (KeyCode::Enter, InputMode::Add) => {
-                        let tasks_filtered = app.filter_tasks(&filter);
+                    (_, KeyCode::Enter, InputMode::Add) => {
+                        let tasks_filtered = app.filter_tasks(&query);
                         let (current_status, current_index) =
                             if let Some(current_task) = tasks_filtered.get(current_index) {
                                 let original_index = app
                                     .tasks
                                     .iter()
-                                    .position(|t| t.description == current_task.description)
+                                    .position(|t| t.id == current_task.id)
                                     .unwrap();
                                 (Some(current_task.status.clone()), Some(original_index))
                             } else {
                                 (None, None)
                             };
 
-                        app.add_task(input.clone(), current_status, current_index);
-                        app.reorder_tasks();
-                        let _ = app.save_to_file(&todo_file_path).unwrap();
-                        input_mode = InputMode::View;
-                        input.clear();
+                        history.push(&input_mode, input.text());
+                        let description = input.text().to_string();
+                        match reject_dependency_cycle(&mut app, |app| {
+                            app.add_task(description, current_status, current_index)
+                        }) {
+                            Ok(()) => {
+                                app.reorder_tasks();
+                                app.save_to_file(&todo_file_path).unwrap();
+                                input_mode = InputMode::View;
+                                input.clear();
+                            }
+                            Err(err) => {
+                                // Keep the prompt open so the dependencies can be fixed
+                                status_message = Some(err);
+                                message_time = Some(Instant::now());
+                            }
+                        }
                     }
-                    (KeyCode::Enter, InputMode::Edit) => {
-                        let tasks_filtered = app.filter_tasks(&filter);
+                    (_, KeyCode::Enter, InputMode::Edit) => {
+                        let tasks_filtered = app.filter_tasks(&query);
                         if let Some(task) = tasks_filtered.get(current_index) {
-                            let original_index = app
-                                .tasks
-                                .iter()
-                                .position(|t| t.description == task.description)
-                                .unwrap();
-                            app.edit_task(original_index, input.clone());
-                            let _ = app.save_to_file(&todo_file_path);
-                            input_mode = InputMode::View;
+                            let original_index =
+                                app.tasks.iter().position(|t| t.id == task.id).unwrap();
+                            history.push(&input_mode, input.text());
+                            let description = input.text().to_string();
+                            match reject_dependency_cycle(&mut app, |app| {
+                                app.edit_task(original_index, description)
+                            }) {
+                                Ok(()) => {
+                                    let _ = app.save_to_file(&todo_file_path);
+                                    input_mode = InputMode::View;
+                                    input.clear();
+                                }
+                                Err(err) => {
+                                    status_message = Some(err);
+                                    message_time = Some(Instant::now());
+                                }
+                            }
+                        } else {
+                            input.clear();
                         }
-                        input.clear();
                     }
-                    (KeyCode::Enter, InputMode::Filter) => {
-                        filter = input.clone();
-                        input_mode = InputMode::View;
+                    (_, KeyCode::Enter, InputMode::Filter) => match Query::parse(input.text()) {
+                        Ok(_) => {
+                            history.push(&input_mode, input.text());
+                            filters.filter = input.text().to_string();
+                            input_mode = InputMode::View;
+                            current_index = 0;
+                            list_state.select(Some(current_index));
+                        }
+                        Err(err) => {
+                            // Keep the prompt open so the query can be fixed
+                            status_message = Some(format!("Filter error: {}", err));
+                            message_time = Some(Instant::now());
+                        }
+                    },
+                    (
+                        _,
+                        KeyCode::Enter,
+                        InputMode::Tag | InputMode::Prioritize | InputMode::Move,
+                    ) => {
+                        let ids = target_ids(&app, &query, &selection, current_index, None);
+                        let value = input.text().trim();
+                        let count = count_tasks(ids.len());
+                        let result = match input_mode {
+                            InputMode::Tag if value.trim_start_matches('@').is_empty() => {
+                                Err("Tag cannot be empty.".to_string())
+                            }
+                            InputMode::Tag => {
+                                app.tag_tasks(&ids, value);
+                                Ok(format!("Tagged {}.", count))
+                            }
+                            InputMode::Prioritize => {
+                                let mut chars = value.chars();
+                                match (chars.next(), chars.next()) {
+                                    (None, _) => {
+                                        app.prioritize_tasks(&ids, None);
+                                        Ok(format!("Cleared the priority of {}.", count))
+                                    }
+                                    (Some(p), None) if p.is_ascii_alphabetic() => {
+                                        let p = p.to_ascii_uppercase();
+                                        app.prioritize_tasks(&ids, Some(p));
+                                        Ok(format!("Set priority ({}) on {}.", p, count))
+                                    }
+                                    _ => Err("Priority must be a letter from A to Z.".to_string()),
+                                }
+                            }
+                            _ => {
+                                app.move_tasks(&ids, value);
+                                match value.trim_start_matches('+') {
+                                    "" => Ok(format!("Removed the project of {}.", count)),
+                                    project => Ok(format!("Moved {} to +{}.", count, project)),
+                                }
+                            }
+                        };
+                        match result {
+                            Ok(message) => {
+                                let change = match input_mode {
+                                    InputMode::Tag => Action::Tag,
+                                    InputMode::Prioritize => Action::Prioritize,
+                                    _ => Action::Move,
+                                };
+                                key_sequence.record(change, value);
+                                let _ = app.save_to_file(&todo_file_path);
+                                status_message = Some(message);
+                                selection.clear();
+                                input_mode = InputMode::View;
+                                input.clear();
+                            }
+                            // Keep the prompt open so the value can be fixed
+                            Err(message) => status_message = Some(message),
+                        }
+                        message_time = Some(Instant::now());
                     }
-                    (KeyCode::Char(c), InputMode::Add | InputMode::Filter | InputMode::Edit) => {
-                        input.push(c);
+                    (_, KeyCode::Enter, InputMode::SaveView) => {
+                        let view = SavedView {
+                            name: input.text().trim().to_string(),
+                            query: query.clone(),
+                        };
+                        if view.name.is_empty() {
+                            status_message = Some("View name cannot be empty.".to_string());
+                        } else if Config::append_view(&config_file_path, &view).is_ok() {
+                            status_message = Some(format!("Saved view '{}'.", view.name));
+                            filters.views.push(view);
+                            filters.active_view = Some(filters.views.len() - 1);
+                            filters.filter.clear();
+                            app.set_sort(preferences.sort(filters.list_name()));
+                            input_mode = InputMode::View;
+                            input.clear();
+                        } else {
+                            status_message = Some("Saving view failed.".to_string());
+                        }
+                        message_time = Some(Instant::now());
                     }
-                    (KeyCode::Backspace, InputMode::Add | InputMode::Filter | InputMode::Edit) => {
-                        input.pop();
+                    (
+                        _,
+                        KeyCode::Up | KeyCode::Down,
+                        InputMode::Add | InputMode::Edit | InputMode::Filter,
+                    ) => {
+                        let changed = if key.code == KeyCode::Up {
+                            history.older(&input_mode, &mut input)
+                        } else {
+                            history.newer(&input_mode, &mut input)
+                        };
+                        if changed && matches!(input_mode, InputMode::Filter) {
+                            current_index = 0;
+                            list_state.select(Some(current_index));
+                        }
                     }
-                    (KeyCode::Esc, _) => {
-                        input.clear();
-                        input_mode = InputMode::View;
+                    (
+                        None,
+                        KeyCode::Char('r'),
+                        InputMode::Add | InputMode::Edit | InputMode::Filter,
+                    ) if key.modifiers == KeyModifiers::CONTROL => {
+                        if !history.search(&input_mode, &mut input) {
+                            status_message = Some("No earlier match in history.".to_string());
+                            message_time = Some(Instant::now());
+                        } else if let InputMode::Filter = input_mode {
+                            current_index = 0;
+                            list_state.select(Some(current_index));
+                        }
                     }
-                    (KeyCode::Char('c'), _) if key.modifiers == KeyModifiers::CONTROL => {
-                        input_mode = InputMode::View;
-                        input.clear();
+                    (
+                        _,
+                        _,
+                        InputMode::Add
+                        | InputMode::Filter
+                        | InputMode::Edit
+                        | InputMode::SaveView
+                        | InputMode::Tag
+                        | InputMode::Prioritize
+                        | InputMode::Move
+                        | InputMode::SnoozeUntil,
+                    ) if input.handle_key(&key) => {
+                        history.reset();
+                        if let InputMode::Filter = input_mode {
+                            current_index = 0;
+                            list_state.select(Some(current_index));
+                        }
                     }
-                    _ => {}
-                }
-                if reset_dialog {
-                    match key.code {
-                        KeyCode::Char('y') => {
-                            let current_date = Local::now().format("%Y-%m-%d").to_string();
-                            let backup_file_name = format!("todo.{}.json", current_date);
-                            let backup_file_path = todo_file_path.with_file_name(backup_file_name);
-                            if fs::copy(&todo_file_path, backup_file_path).is_ok() {
-                                fs::write(&todo_file_path, "[]")
-                                    .expect("Unable to clear todo file");
-                                status_message =
-                                    Some("Backup created and todo list reset.".to_string());
-                            } else {
-                                status_message = Some("Backup failed. Reset canceled.".to_string());
-                            }
-
-                            message_time = Some(Instant::now());
-                            reset_dialog = false;
+                    (_, KeyCode::Esc, _) => {
+                        // Leaving the filter prompt restores the previously applied filter
+                        if let InputMode::Filter = input_mode {
+                            current_index = 0;
+                            list_state.select(Some(current_index));
                         }
-                        KeyCode::Char('n') => {
-                            // Cancel the reset process
-                            status_message = Some("Reset canceled.".to_string());
-                            message_time = Some(Instant::now());
-                            reset_dialog = false;
+                        if let InputMode::View = input_mode {
+                            selection.clear();
                         }
-                        _ => {}
+                        history.reset();
+                        input.clear();
+                        input_mode = InputMode::View;
                     }
+                    _ => {}
                 }
             }
         }
@@ -276,8 +1471,201 @@ fn main() -> Result<(), io::Error> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds typed characters to `keys` with the default bindings, returning the completed
+    /// commands as `(action, count)`.
+    fn type_keys(keys: &mut KeySequence, typed: &str) -> Vec<(Action, Option<usize>)> {
+        let keymap = Keymap::default();
+        typed
+            .chars()
+            .filter_map(|c| {
+                let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+                keys.feed(keymap.action(&key), &key, &DOUBLED)
+            })
+            .map(|command| (command.action, command.count))
+            .collect()
+    }
+
+    #[test]
+    fn count_prefixes_apply_to_the_next_key() {
+        let mut keys = KeySequence::default();
+        assert_eq!(type_keys(&mut keys, "5j"), [(Action::Down, Some(5))]);
+        assert_eq!(type_keys(&mut keys, "12G"), [(Action::Bottom, Some(12))]);
+        assert_eq!(type_keys(&mut keys, "k"), [(Action::Up, None)]);
+        assert_eq!(type_keys(&mut keys, "0"), []);
+        let tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        let command = keys.feed(Some(Action::NextView), &tab, &DOUBLED).unwrap();
+        assert_eq!((command.action, command.count), (Action::NextView, Some(0)));
+    }
+
+    #[test]
+    fn alt_digits_switch_views_at_once() {
+        let mut keys = KeySequence::default();
+        let alt_2 = KeyEvent::new(KeyCode::Char('2'), KeyModifiers::ALT);
+        type_keys(&mut keys, "5d");
+        let command = keys.feed(None, &alt_2, &DOUBLED).unwrap();
+        assert_eq!((command.action, command.count), (Action::NextView, Some(2)));
+        // The count and operator typed before are dropped
+        assert_eq!(type_keys(&mut keys, "j"), [(Action::Down, None)]);
+    }
+
+    #[test]
+    fn doubled_keys_wait_for_the_second_press() {
+        let mut keys = KeySequence::default();
+        assert_eq!(type_keys(&mut keys, "g"), []);
+        assert_eq!(type_keys(&mut keys, "g"), [(Action::Top, None)]);
+        assert_eq!(type_keys(&mut keys, "3dd"), [(Action::Delete, Some(3))]);
+        assert_eq!(type_keys(&mut keys, "5gg"), [(Action::Top, Some(5))]);
+    }
+
+    #[test]
+    fn other_keys_cancel_a_pending_operator() {
+        let mut keys = KeySequence::default();
+        assert_eq!(type_keys(&mut keys, "dj"), []);
+        assert_eq!(type_keys(&mut keys, "j"), [(Action::Down, None)]);
+        assert_eq!(type_keys(&mut keys, "3gd"), []);
+        // The count does not survive the cancelled operator
+        assert_eq!(type_keys(&mut keys, "j"), [(Action::Down, None)]);
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        type_keys(&mut keys, "4");
+        assert_eq!(keys.feed(None, &esc, &DOUBLED), None);
+        assert_eq!(type_keys(&mut keys, "k"), [(Action::Up, None)]);
+    }
+
+    #[test]
+    fn operators_can_act_at_once() {
+        let mut keys = KeySequence::default();
+        let key = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE);
+        let command = keys.feed(Some(Action::Delete), &key, &[Action::Top]);
+        assert_eq!(command.map(|c| c.action), Some(Action::Delete));
+    }
+
+    #[test]
+    fn dot_repeats_the_last_change() {
+        let mut keys = KeySequence::default();
+        assert_eq!(type_keys(&mut keys, "."), []);
+        assert_eq!(
+            type_keys(&mut keys, "3dd.j."),
+            [
+                (Action::Delete, Some(3)),
+                (Action::Delete, Some(3)),
+                (Action::Down, None),
+                (Action::Delete, Some(3)),
+            ]
+        );
+        // A count replaces the one of the repeated change
+        assert_eq!(
+            type_keys(&mut keys, " 2.."),
+            [
+                (Action::Toggle, None),
+                (Action::Toggle, Some(2)),
+                (Action::Toggle, Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn dot_repeats_changes_made_through_prompts() {
+        let mut keys = KeySequence::default();
+        keys.record(Action::Tag, "home");
+        let key = KeyEvent::new(KeyCode::Char('.'), KeyModifiers::NONE);
+        let command = keys.feed(Some(Action::Repeat), &key, &DOUBLED).unwrap();
+        assert_eq!(command.action, Action::Tag);
+        assert_eq!(command.value.as_deref(), Some("home"));
+    }
+
+    #[test]
+    fn motions_stay_within_the_list() {
+        // 100 tasks, 20 rows shown starting at task 10, task 15 highlighted
+        let target = |action, count| motion_target(action, count, 15, 100, 10, 20);
+        assert_eq!(target(Action::Down, Some(5)), Some(20));
+        assert_eq!(target(Action::Down, Some(500)), Some(99));
+        assert_eq!(target(Action::Up, Some(500)), Some(0));
+        assert_eq!(target(Action::Top, None), Some(0));
+        assert_eq!(target(Action::Top, Some(5)), Some(4));
+        assert_eq!(target(Action::Bottom, None), Some(99));
+        assert_eq!(target(Action::Bottom, Some(500)), Some(99));
+        assert_eq!(target(Action::HalfPageDown, None), Some(25));
+        assert_eq!(target(Action::HalfPageDown, Some(2)), Some(35));
+        assert_eq!(target(Action::HalfPageUp, None), Some(5));
+        assert_eq!(target(Action::PageDown, None), Some(35));
+        assert_eq!(target(Action::PageUp, None), Some(0));
+        assert_eq!(motion_target(Action::Down, None, 0, 0, 0, 20), None);
+    }
+
+    #[test]
+    fn screen_motions_use_the_shown_tasks() {
+        let target = |action, count| motion_target(action, count, 15, 100, 10, 20);
+        assert_eq!(target(Action::ScreenTop, None), Some(10));
+        assert_eq!(target(Action::ScreenTop, Some(3)), Some(12));
+        assert_eq!(target(Action::ScreenMiddle, None), Some(19));
+        assert_eq!(target(Action::ScreenBottom, None), Some(29));
+        assert_eq!(target(Action::ScreenBottom, Some(3)), Some(27));
+        // A list shorter than the screen
+        let target = |action| motion_target(action, None, 0, 5, 0, 20);
+        assert_eq!(target(Action::ScreenBottom), Some(4));
+        assert_eq!(target(Action::ScreenMiddle), Some(2));
+    }
+
+    #[test]
+    fn clicks_map_to_shown_tasks() {
+        let area = Rect::new(1, 4, 40, 10);
+        assert_eq!(clicked_task(area, 0, 30, 10, 4), Some((0, false)));
+        assert_eq!(clicked_task(area, 12, 30, 10, 6), Some((14, false)));
+        assert_eq!(clicked_task(area, 12, 30, 4, 6), Some((14, true)));
+        // Below the last task, on the border or outside the list
+        assert_eq!(clicked_task(area, 0, 3, 10, 7), None);
+        assert_eq!(clicked_task(area, 0, 30, 0, 5), None);
+        assert_eq!(clicked_task(area, 0, 30, 10, 14), None);
+    }
+
+    #[test]
+    fn scroll_offset_keeps_the_selection_in_view() {
+        assert_eq!(scroll_offset(0, 25, 100, 20), 6);
+        assert_eq!(scroll_offset(10, 5, 100, 20), 5);
+        assert_eq!(scroll_offset(10, 29, 100, 20), 10);
+        assert_eq!(scroll_offset(50, 2, 3, 20), 2);
+    }
+
+    #[test]
+    fn new_dependency_cycles_are_undone() {
+        let mut app = TodoApp::new();
+        for description in ["a", "b dep:1", "c dep:2"] {
+            app.add_task(description.to_string(), None, None);
+        }
+        let edit = |app: &mut TodoApp, id: u64, description: &str| {
+            let index = app.tasks.iter().position(|t| t.id == id).unwrap();
+            reject_dependency_cycle(app, |app| app.edit_task(index, description.to_string()))
+        };
+        let description = |app: &TodoApp, id: u64| {
+            let task = app.tasks.iter().find(|t| t.id == id).unwrap();
+            task.description.clone()
+        };
+
+        let err = edit(&mut app, 1, "a dep:3").unwrap_err();
+        assert_eq!(err, "Dependency cycle #1 -> #3 -> #2 -> #1.");
+        assert_eq!(description(&app, 1), "a");
+        let err = edit(&mut app, 3, "c dep:3").unwrap_err();
+        assert_eq!(err, "Dependency cycle #3 -> #3.");
+        assert_eq!(description(&app, 3), "c dep:2");
+
+        // A cycle that was there before does not stop other changes
+        app.tasks
+            .iter_mut()
+            .find(|t| t.id == 1)
+            .unwrap()
+            .description = "a dep:2".to_string();
+        assert!(edit(&mut app, 3, "c dep:2 @home").is_ok());
+        assert_eq!(description(&app, 3), "c dep:2 @home");
+    }
+}
@@ -0,0 +1,123 @@
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+/// How long the bell flash stays on the gauge after an interval ends.
+const FLASH: Duration = Duration::from_secs(2);
+
+/// Interval lengths in minutes, set in the `[pomodoro]` table of the config file.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    pub work: u64,
+    #[serde(rename = "break")]
+    pub short_break: u64,
+    pub long_break: u64,
+    /// Every how many work intervals the break is a long one.
+    pub long_break_every: u32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> PomodoroConfig {
+        PomodoroConfig {
+            work: 25,
+            short_break: 5,
+            long_break: 15,
+            long_break_every: 4,
+        }
+    }
+}
+
+impl PomodoroConfig {
+    /// Raises lengths below a minute to one, as such intervals would end as soon as they
+    /// start, over and over.
+    pub fn clamped(self) -> PomodoroConfig {
+        PomodoroConfig {
+            work: self.work.max(1),
+            short_break: self.short_break.max(1),
+            long_break: self.long_break.max(1),
+            long_break_every: self.long_break_every,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work,
+    Break,
+}
+
+/// A running pomodoro on one task, alternating work and break intervals until stopped.
+pub struct Pomodoro {
+    pub task_id: u64,
+    pub phase: Phase,
+    /// Work intervals finished since it was started.
+    pub finished: u32,
+    config: PomodoroConfig,
+    started: Instant,
+    length: Duration,
+    /// When the last interval ended.
+    ended: Option<Instant>,
+}
+
+impl Pomodoro {
+    pub fn start(task_id: u64, config: PomodoroConfig) -> Pomodoro {
+        Pomodoro {
+            task_id,
+            phase: Phase::Work,
+            finished: 0,
+            config,
+            started: Instant::now(),
+            length: minutes(config.work),
+            ended: None,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.length.saturating_sub(self.started.elapsed())
+    }
+
+    /// How much of the current interval has passed, from 0 to 1.
+    pub fn ratio(&self) -> f64 {
+        if self.length.is_zero() {
+            return 1.0;
+        }
+        (self.started.elapsed().as_secs_f64() / self.length.as_secs_f64()).min(1.0)
+    }
+
+    /// Whether an interval ended a moment ago.
+    pub fn flashing(&self) -> bool {
+        self.ended.is_some_and(|ended| ended.elapsed() < FLASH)
+    }
+
+    /// Starts the next interval once the current one is over, returning the phase that ended.
+    pub fn tick(&mut self) -> Option<Phase> {
+        if self.started.elapsed() < self.length {
+            return None;
+        }
+        let ended = self.phase;
+        let now = Instant::now();
+        self.ended = Some(now);
+        self.started = now;
+        match ended {
+            Phase::Work => {
+                self.finished += 1;
+                self.phase = Phase::Break;
+                let every = self.config.long_break_every.max(1);
+                self.length = if self.finished % every == 0 {
+                    minutes(self.config.long_break)
+                } else {
+                    minutes(self.config.short_break)
+                };
+            }
+            Phase::Break => {
+                self.phase = Phase::Work;
+                self.length = minutes(self.config.work);
+            }
+        }
+        Some(ended)
+    }
+}
+
+fn minutes(minutes: u64) -> Duration {
+    Duration::from_secs(minutes * 60)
+}
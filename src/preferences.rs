@@ -0,0 +1,42 @@
+use crate::sort::Sort;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Choices made in the TUI that are remembered between sessions.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Preferences {
+    pub show_details: bool,
+    /// The sort order of each list, by view name or `All`.
+    pub sorts: HashMap<String, Sort>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Preferences {
+    /// Loads the preferences kept next to the todo file, e.g. `~/todo.prefs.json`.
+    pub fn load(todo_file_path: &Path) -> Preferences {
+        let path = todo_file_path.with_file_name("todo.prefs.json");
+        let mut preferences: Preferences = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        preferences.path = path;
+        preferences
+    }
+
+    /// The sort order remembered for a list, see [`crate::ui::Filters::list_name`].
+    pub fn sort(&self, list_name: &str) -> Sort {
+        self.sorts.get(list_name).copied().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(&self.path, data)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,546 @@
+use crate::task::{Task, TaskStatus};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use regex::Regex;
+use std::{fmt, iter::Peekable, vec::IntoIter};
+
+/// A parsed filter such as `status:pending and (+work or @home) not re:/^Fix/`.
+///
+/// Adjacent terms are combined with `and`; an empty query matches every task. Snoozed
+/// tasks only match queries that mention `is:snoozed`.
+pub struct Query {
+    expr: Option<Expr>,
+    shows_snoozed: bool,
+}
+
+#[derive(Debug)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+enum Term {
+    Text(String),
+    Project(String),
+    Tag(String),
+    Status(TaskStatus),
+    Priority(char),
+    Due(Cmp, DateTime<Local>),
+    /// `due:none`, tasks without a due date.
+    NoDue,
+    Created(Cmp, DateTime<Local>),
+    Regex(Regex),
+    /// `is:blocked`, tasks with dependencies that are not done.
+    Blocked,
+    /// `is:actionable`, tasks that are not done and not blocked.
+    Actionable,
+    /// `is:snoozed`, tasks hidden until a later time.
+    Snoozed,
+}
+
+#[derive(Clone, Copy)]
+enum Cmp {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Word(String),
+    Phrase(String),
+    Regex(String),
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?.into_iter().peekable(),
+        };
+        if parser.peek().is_none() {
+            return Ok(Query {
+                expr: None,
+                shows_snoozed: false,
+            });
+        }
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(ParseError(format!("Unexpected {}", describe(token))));
+        }
+        Ok(Query {
+            shows_snoozed: expr.mentions_snoozed(),
+            expr: Some(expr),
+        })
+    }
+
+    /// A query that matches descriptions containing `text` verbatim.
+    pub fn text(text: &str) -> Query {
+        Query {
+            expr: Some(Expr::Term(Term::Text(text.to_string()))),
+            shows_snoozed: false,
+        }
+    }
+
+    /// Whether `task`, one of `tasks`, matches. The other tasks are needed to tell if
+    /// it is blocked.
+    pub fn matches(&self, task: &Task, tasks: &[Task]) -> bool {
+        if task.is_snoozed(Local::now()) && !self.shows_snoozed {
+            return false;
+        }
+        self.expr
+            .as_ref()
+            .map_or(true, |expr| expr.matches(task, tasks))
+    }
+}
+
+impl Expr {
+    fn mentions_snoozed(&self) -> bool {
+        match self {
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+                lhs.mentions_snoozed() || rhs.mentions_snoozed()
+            }
+            Expr::Not(expr) => expr.mentions_snoozed(),
+            Expr::Term(term) => matches!(term, Term::Snoozed),
+        }
+    }
+
+    fn matches(&self, task: &Task, tasks: &[Task]) -> bool {
+        match self {
+            Expr::And(lhs, rhs) => lhs.matches(task, tasks) && rhs.matches(task, tasks),
+            Expr::Or(lhs, rhs) => lhs.matches(task, tasks) || rhs.matches(task, tasks),
+            Expr::Not(expr) => !expr.matches(task, tasks),
+            Expr::Term(term) => term.matches(task, tasks),
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, task: &Task, tasks: &[Task]) -> bool {
+        match self {
+            Term::Text(text) => {
+                task.description.contains(text.as_str()) || task.notes.contains(text.as_str())
+            }
+            Term::Project(project) => task.projects().any(|p| p == project),
+            Term::Tag(tag) => task.tags().any(|t| t == tag),
+            Term::Status(status) => task.status == *status,
+            Term::Priority(priority) => task.priority() == Some(*priority),
+            Term::Due(cmp, date) => task
+                .due()
+                .map(start_of_day)
+                .is_some_and(|due| cmp.compare(due, *date)),
+            Term::NoDue => task.due().is_none(),
+            Term::Created(cmp, date) => task
+                .created_at
+                .is_some_and(|created| cmp.compare(created, *date)),
+            Term::Regex(regex) => regex.is_match(&task.description) || regex.is_match(&task.notes),
+            Term::Blocked => !task.blockers(tasks).is_empty(),
+            Term::Actionable => task.status != TaskStatus::Done && task.blockers(tasks).is_empty(),
+            Term::Snoozed => task.is_snoozed(Local::now()),
+        }
+    }
+}
+
+impl Cmp {
+    fn compare(self, lhs: DateTime<Local>, rhs: DateTime<Local>) -> bool {
+        match self {
+            Cmp::Lt => lhs < rhs,
+            Cmp::Le => lhs <= rhs,
+            Cmp::Eq => lhs.date_naive() == rhs.date_naive(),
+            Cmp::Ge => lhs >= rhs,
+            Cmp::Gt => lhs > rhs,
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+        Token::And => "'and'".to_string(),
+        Token::Or => "'or'".to_string(),
+        Token::Not => "'not'".to_string(),
+        Token::Word(word) => format!("'{}'", word),
+        Token::Phrase(phrase) => format!("\"{}\"", phrase),
+        Token::Regex(regex) => format!("re:/{}/", regex),
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '-' => {
+                chars.next();
+                match chars.peek() {
+                    Some(next) if !next.is_whitespace() => tokens.push(Token::Not),
+                    _ => tokens.push(Token::Word("-".to_string())),
+                }
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => phrase.push(c),
+                        None => return Err(ParseError("Unterminated quote".to_string())),
+                    }
+                }
+                tokens.push(Token::Phrase(phrase));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                    if word == "re:/" {
+                        tokens.push(Token::Regex(read_regex(&mut chars)?));
+                        word.clear();
+                        break;
+                    }
+                }
+                if word.is_empty() {
+                    continue;
+                }
+                let token = match word.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Word(word),
+                };
+                tokens.push(token);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a regex body up to the closing `/`; `\/` stands for a literal slash.
+fn read_regex(chars: &mut impl Iterator<Item = char>) -> Result<String, ParseError> {
+    let mut pattern = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' => return Ok(pattern),
+            '\\' => match chars.next() {
+                Some('/') => pattern.push('/'),
+                Some(c) => {
+                    pattern.push('\\');
+                    pattern.push(c);
+                }
+                None => break,
+            },
+            c => pattern.push(c),
+        }
+    }
+    Err(ParseError(
+        "Unterminated regex, expected closing '/'".to_string(),
+    ))
+}
+
+struct Parser {
+    tokens: Peekable<IntoIter<Token>>,
+}
+
+impl Parser {
+    fn peek(&mut self) -> Option<&Token> {
+        self.tokens.peek()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        self.tokens.next()
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Or | Token::RParen) | None => break,
+                Some(_) => {}
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err(ParseError("Expected ')'".to_string())),
+                }
+            }
+            Some(Token::Word(word)) => parse_term(&word).map(Expr::Term),
+            Some(Token::Phrase(phrase)) => Ok(Expr::Term(Term::Text(phrase))),
+            Some(Token::Regex(pattern)) => Regex::new(&pattern)
+                .map(|regex| Expr::Term(Term::Regex(regex)))
+                .map_err(|err| {
+                    // Syntax errors span several lines; the last one holds the reason
+                    let err = err.to_string();
+                    let reason = err.lines().last().unwrap_or_default();
+                    ParseError(format!(
+                        "Invalid regex: {}",
+                        reason.trim_start_matches("error: ")
+                    ))
+                }),
+            Some(token) => Err(ParseError(format!("Unexpected {}", describe(&token)))),
+            None => Err(ParseError("Unexpected end of query".to_string())),
+        }
+    }
+}
+
+fn parse_term(word: &str) -> Result<Term, ParseError> {
+    if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+        return Ok(Term::Project(project.to_string()));
+    }
+    if let Some(tag) = word.strip_prefix('@').filter(|t| !t.is_empty()) {
+        return Ok(Term::Tag(tag.to_string()));
+    }
+
+    let split = word.find([':', '<', '>', '=']);
+    let Some(split) = split else {
+        return Ok(Term::Text(word.to_string()));
+    };
+    let (field, rest) = word.split_at(split);
+    let (cmp, value) = if let Some(value) = rest.strip_prefix("<=") {
+        (Cmp::Le, value)
+    } else if let Some(value) = rest.strip_prefix(">=") {
+        (Cmp::Ge, value)
+    } else if let Some(value) = rest.strip_prefix('<') {
+        (Cmp::Lt, value)
+    } else if let Some(value) = rest.strip_prefix('>') {
+        (Cmp::Gt, value)
+    } else {
+        (Cmp::Eq, &rest[1..])
+    };
+
+    match field.to_lowercase().as_str() {
+        "is" if value.eq_ignore_ascii_case("blocked") => Ok(Term::Blocked),
+        "is" if value.eq_ignore_ascii_case("actionable") => Ok(Term::Actionable),
+        "is" if value.eq_ignore_ascii_case("snoozed") => Ok(Term::Snoozed),
+        "status" | "is" => parse_status(value).map(Term::Status),
+        "prio" | "priority" => {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(p), None) if p.is_ascii_alphabetic() => {
+                    Ok(Term::Priority(p.to_ascii_uppercase()))
+                }
+                _ => Err(ParseError(format!("Invalid priority '{}'", value))),
+            }
+        }
+        "due" if matches!(cmp, Cmp::Eq) && value.eq_ignore_ascii_case("none") => Ok(Term::NoDue),
+        "due" => parse_date(value, true).map(|date| Term::Due(cmp, date)),
+        "created" => parse_date(value, false).map(|date| Term::Created(cmp, date)),
+        // Anything else (e.g. "Project:") is plain text, as before the query language
+        _ => Ok(Term::Text(word.to_string())),
+    }
+}
+
+fn parse_status(value: &str) -> Result<TaskStatus, ParseError> {
+    match value.to_lowercase().as_str() {
+        "undone" | "todo" | "open" => Ok(TaskStatus::Undone),
+        "pending" => Ok(TaskStatus::Pending),
+        "done" | "completed" => Ok(TaskStatus::Done),
+        _ => Err(ParseError(format!(
+            "Unknown status '{}' (expected undone, pending or done)",
+            value
+        ))),
+    }
+}
+
+/// Parses `YYYY-MM-DD`, `today`, `tomorrow`, `yesterday` or a relative offset such as
+/// `3d`, `2w` or `12h`. Offsets point into the future when `future` is set (due dates)
+/// and into the past otherwise (creation dates), so `created>7d` means "in the last week".
+pub fn parse_date(value: &str, future: bool) -> Result<DateTime<Local>, ParseError> {
+    let today = Local::now().date_naive();
+    match value.to_lowercase().as_str() {
+        "today" => return Ok(start_of_day(today)),
+        "tomorrow" => return Ok(start_of_day(today + Duration::days(1))),
+        "yesterday" => return Ok(start_of_day(today - Duration::days(1))),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(start_of_day(date));
+    }
+
+    let invalid = || ParseError(format!("Invalid date '{}'", value));
+    let unit = value.chars().last().ok_or_else(invalid)?;
+    let amount: i64 = value[..value.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    let offset = match unit {
+        'h' => Duration::try_hours(amount),
+        'd' => Duration::try_days(amount),
+        'w' => Duration::try_weeks(amount),
+        _ => return Err(invalid()),
+    };
+    // Offsets too large for a date are an error rather than an overflow
+    let out_of_range = || ParseError(format!("Date '{}' is out of range", value));
+    let offset = offset.ok_or_else(out_of_range)?;
+    let date = if future {
+        Local::now().checked_add_signed(offset)
+    } else {
+        Local::now().checked_sub_signed(offset)
+    };
+    date.ok_or_else(out_of_range)
+}
+
+pub fn start_of_day(date: NaiveDate) -> DateTime<Local> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&midnight))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The descriptions of `descriptions` that `query` matches, as undone tasks.
+    fn matching<'a>(query: &str, descriptions: &[&'a str]) -> Vec<&'a str> {
+        let query = Query::parse(query).unwrap();
+        let tasks: Vec<Task> = descriptions
+            .iter()
+            .enumerate()
+            .map(|(i, d)| Task::new(i as u64 + 1, d.to_string(), TaskStatus::Undone))
+            .collect();
+        descriptions
+            .iter()
+            .zip(&tasks)
+            .filter(|(_, task)| query.matches(task, &tasks))
+            .map(|(d, _)| *d)
+            .collect()
+    }
+
+    fn error(query: &str) -> String {
+        match Query::parse(query) {
+            Ok(_) => panic!("'{}' parsed", query),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let tasks = ["a", "b", "c", "b c", "a c"];
+        assert_eq!(matching("a or b c", &tasks), ["a", "b c", "a c"]);
+        assert_eq!(matching("(a or b) c", &tasks), ["b c", "a c"]);
+    }
+
+    #[test]
+    fn minus_negates_a_term() {
+        let tasks = ["Shop @home", "Call @work", "Read"];
+        assert_eq!(matching("-@home", &tasks), ["Call @work", "Read"]);
+        assert_eq!(matching("not @home @work", &tasks), ["Call @work"]);
+        assert_eq!(matching("a - b", &["a - b", "a b"]), ["a - b"]);
+    }
+
+    #[test]
+    fn regexes_take_escaped_slashes() {
+        let tasks = ["a/b", "ab", "A/B"];
+        assert_eq!(matching(r"re:/a\/b/", &tasks), ["a/b"]);
+        assert_eq!(matching(r"re:/(?i)^a\/b$/", &tasks), ["a/b", "A/B"]);
+        assert_eq!(matching(r"re:/a\.b/", &["a.b", "axb"]), ["a.b"]);
+    }
+
+    #[test]
+    fn due_none_matches_tasks_without_a_due_date() {
+        let tasks = ["Pay due:2020-01-31", "Read"];
+        assert_eq!(matching("due:none", &tasks), ["Read"]);
+        assert_eq!(matching("-due:none", &tasks), ["Pay due:2020-01-31"]);
+    }
+
+    #[test]
+    fn due_dates_compare_to_absolute_and_relative_dates() {
+        let today = Local::now().date_naive();
+        let due = |days: i64| format!("due:{}", today + Duration::days(days));
+        let (past, now, soon) = (due(-3), due(0), due(5));
+        let tasks = [past.as_str(), now.as_str(), soon.as_str()];
+        assert_eq!(matching("due<today", &tasks), [past.as_str()]);
+        assert_eq!(matching("due:today", &tasks), [now.as_str()]);
+        assert_eq!(
+            matching("due<=1w", &tasks),
+            [past.as_str(), now.as_str(), soon.as_str()]
+        );
+        assert_eq!(matching("due>2d", &tasks), [soon.as_str()]);
+        let query = format!("due>={}", today + Duration::days(1));
+        assert_eq!(matching(&query, &tasks), [soon.as_str()]);
+    }
+
+    #[test]
+    fn relative_dates_point_forward_for_due_and_back_for_created() {
+        let now = Local::now();
+        assert!(parse_date("2d", true).unwrap() > now + Duration::days(1));
+        assert!(parse_date("2d", false).unwrap() < now - Duration::days(1));
+        assert!(parse_date("12h", true).unwrap() < now + Duration::days(1));
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(parse_date("2024-02-29", true).unwrap(), start_of_day(date));
+    }
+
+    #[test]
+    fn errors_name_the_problem() {
+        assert_eq!(error("(a or b"), "Expected ')'");
+        assert_eq!(error("a or"), "Unexpected end of query");
+        assert_eq!(error("a )"), "Unexpected ')'");
+        assert_eq!(error("\"a b"), "Unterminated quote");
+        assert_eq!(error("re:/a"), "Unterminated regex, expected closing '/'");
+        assert!(error("re:/(/").starts_with("Invalid regex: "));
+        assert_eq!(error("prio:AB"), "Invalid priority 'AB'");
+        assert_eq!(
+            error("is:later"),
+            "Unknown status 'later' (expected undone, pending or done)"
+        );
+        assert_eq!(error("due<2024-02-30"), "Invalid date '2024-02-30'");
+        assert_eq!(error("created>5y"), "Invalid date '5y'");
+        assert_eq!(error("due<100000000d"), "Date '100000000d' is out of range");
+    }
+}
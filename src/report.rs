@@ -0,0 +1,185 @@
+use crate::task::{Task, TaskStatus};
+use chrono::{DateTime, Local, NaiveDate};
+use std::fmt::Write;
+
+/// How a report is written out.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Markdown,
+}
+
+/// The tasks of one project in a report.
+pub struct ProjectReport<'a> {
+    /// `None` for tasks without a `+project`.
+    pub project: Option<&'a str>,
+    /// Completed in the period, going by the recorded completion time.
+    pub completed: Vec<&'a Task>,
+    /// Created in the period.
+    pub created: Vec<&'a Task>,
+    /// Not done yet and not overdue, whenever they were created.
+    pub pending: Vec<&'a Task>,
+    /// Not done and due before today.
+    pub overdue: Vec<&'a Task>,
+}
+
+impl ProjectReport<'_> {
+    fn is_empty(&self) -> bool {
+        self.completed.is_empty()
+            && self.created.is_empty()
+            && self.pending.is_empty()
+            && self.overdue.is_empty()
+    }
+}
+
+/// What happened to a task list since some point in time, see
+/// [`crate::app::TodoApp::report`].
+pub struct Report<'a> {
+    pub since: DateTime<Local>,
+    pub today: NaiveDate,
+    /// One entry per project in alphabetical order, tasks without a project last.
+    pub projects: Vec<ProjectReport<'a>>,
+}
+
+impl<'a> Report<'a> {
+    pub fn of(tasks: &'a [Task], since: DateTime<Local>, today: NaiveDate) -> Report<'a> {
+        let mut names: Vec<Option<&str>> = tasks
+            .iter()
+            .flat_map(|task| task.projects().map(Some))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names.push(None);
+
+        let projects = names
+            .into_iter()
+            .map(|project| {
+                let tasks: Vec<&Task> = tasks
+                    .iter()
+                    .filter(|task| match project {
+                        Some(name) => task.projects().any(|p| p == name),
+                        None => task.projects().next().is_none(),
+                    })
+                    .collect();
+                let open = |task: &&Task| task.status != TaskStatus::Done;
+                let overdue = |task: &&Task| task.due().is_some_and(|due| due < today);
+                ProjectReport {
+                    project,
+                    completed: tasks
+                        .iter()
+                        .copied()
+                        .filter(|task| task.completed_at().is_some_and(|at| at >= since))
+                        .collect(),
+                    created: tasks
+                        .iter()
+                        .copied()
+                        .filter(|task| task.created_at.is_some_and(|at| at >= since))
+                        .collect(),
+                    pending: tasks
+                        .iter()
+                        .copied()
+                        .filter(|task| open(task) && !overdue(task))
+                        .collect(),
+                    overdue: tasks
+                        .iter()
+                        .copied()
+                        .filter(|task| open(task) && overdue(task))
+                        .collect(),
+                }
+            })
+            .filter(|project| !project.is_empty())
+            .collect();
+
+        Report {
+            since,
+            today,
+            projects,
+        }
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        let mut out = String::new();
+        let title = format!(
+            "Report {} to {}",
+            self.since.format("%Y-%m-%d %H:%M"),
+            self.today.format("%Y-%m-%d")
+        );
+        // Writing to a String cannot fail
+        match format {
+            Format::Text => {
+                let _ = writeln!(out, "{}\n{}", title, "=".repeat(title.len()));
+            }
+            Format::Markdown => {
+                let _ = writeln!(out, "# {}", title);
+            }
+        }
+        if self.projects.is_empty() {
+            let _ = writeln!(out, "\nNothing to report.");
+        }
+
+        for project in &self.projects {
+            let name = project
+                .project
+                .map_or("No project".to_string(), |name| format!("+{}", name));
+            match format {
+                Format::Text => {
+                    let _ = writeln!(out, "\n{}", name);
+                }
+                Format::Markdown => {
+                    let _ = writeln!(out, "\n## {}", name);
+                }
+            }
+            let sections = [
+                ("Completed", &project.completed),
+                ("Created", &project.created),
+                ("Pending", &project.pending),
+                ("Overdue", &project.overdue),
+            ];
+            for (section, tasks) in sections {
+                if tasks.is_empty() {
+                    continue;
+                }
+                match format {
+                    Format::Text => {
+                        let _ = writeln!(out, "  {} ({})", section, tasks.len());
+                    }
+                    Format::Markdown => {
+                        let _ = writeln!(out, "\n### {} ({})\n", section, tasks.len());
+                    }
+                }
+                for task in tasks.iter() {
+                    let line = self.task_line(task, section == "Completed");
+                    match format {
+                        Format::Text => {
+                            let _ = writeln!(out, "    {} {}", task.status.symbol(), line);
+                        }
+                        Format::Markdown => {
+                            let checked = if task.status == TaskStatus::Done {
+                                "x"
+                            } else {
+                                " "
+                            };
+                            let _ = writeln!(out, "- [{}] {}", checked, line);
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// The description, followed by the completion date or how many days it is overdue.
+    fn task_line(&self, task: &Task, completed: bool) -> String {
+        let note = match (completed, task.completed_at(), task.due()) {
+            (true, Some(at), _) => Some(format!("done {}", at.format("%Y-%m-%d"))),
+            (false, None, Some(due)) if due < self.today => {
+                Some(format!("{} days overdue", (self.today - due).num_days()))
+            }
+            _ => None,
+        };
+        match note {
+            Some(note) => format!("{} ({})", task.description, note),
+            None => task.description.clone(),
+        }
+    }
+}
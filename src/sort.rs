@@ -0,0 +1,130 @@
+use crate::task::Task;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, fmt};
+
+/// What the task list is ordered by.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// The order set with `J`/`K`, see [`Task::position`].
+    #[default]
+    Manual,
+    Created,
+    Alphabetical,
+    Due,
+    Priority,
+    Modified,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 6] = [
+        SortKey::Manual,
+        SortKey::Created,
+        SortKey::Alphabetical,
+        SortKey::Due,
+        SortKey::Priority,
+        SortKey::Modified,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortKey::Manual => "manual",
+            SortKey::Created => "created",
+            SortKey::Alphabetical => "alphabetical",
+            SortKey::Due => "due date",
+            SortKey::Priority => "priority",
+            SortKey::Modified => "last modified",
+        }
+    }
+
+    /// The key choosing this order in the sort menu.
+    pub fn key(self) -> char {
+        match self {
+            SortKey::Manual => 'm',
+            SortKey::Created => 'c',
+            SortKey::Alphabetical => 'a',
+            SortKey::Due => 'd',
+            SortKey::Priority => 'p',
+            SortKey::Modified => 'l',
+        }
+    }
+
+    pub fn from_key(key: char) -> Option<SortKey> {
+        SortKey::ALL.into_iter().find(|k| k.key() == key)
+    }
+}
+
+/// How the task list is sorted; remembered per list in the preferences.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+    /// Keep undone, pending and done tasks apart, sorting within each group.
+    pub group_by_status: bool,
+}
+
+impl Default for Sort {
+    fn default() -> Sort {
+        Sort {
+            key: SortKey::Manual,
+            descending: false,
+            group_by_status: true,
+        }
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key.name())?;
+        if self.descending {
+            f.write_str(", descending")?;
+        }
+        if !self.group_by_status {
+            f.write_str(", ungrouped")?;
+        }
+        Ok(())
+    }
+}
+
+impl Sort {
+    /// Compares two tasks by the sort key alone. Tasks without a due date or priority come
+    /// last in either direction.
+    pub fn compare(&self, a: &Task, b: &Task) -> Ordering {
+        let direction = |ordering: Ordering| {
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+        match self.key {
+            SortKey::Manual => direction(a.position.cmp(&b.position)),
+            SortKey::Created => direction(a.created_at.cmp(&b.created_at)),
+            SortKey::Alphabetical => direction(
+                a.description
+                    .to_lowercase()
+                    .cmp(&b.description.to_lowercase()),
+            ),
+            SortKey::Due => missing_last(a.due(), b.due(), direction),
+            SortKey::Priority => missing_last(a.priority(), b.priority(), direction),
+            SortKey::Modified => {
+                let modified = |t: &Task| t.modified_at.or(t.created_at);
+                direction(modified(a).cmp(&modified(b)))
+            }
+        }
+    }
+}
+
+fn missing_last<T: Ord>(
+    a: Option<T>,
+    b: Option<T>,
+    direction: impl Fn(Ordering) -> Ordering,
+) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => direction(a.cmp(&b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
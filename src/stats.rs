@@ -0,0 +1,269 @@
+use crate::task::{Task, TaskStatus};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime};
+use std::{collections::HashMap, fmt};
+
+/// How many days the daily counts reach back.
+pub const DAYS: usize = 30;
+
+/// Tasks created and completed on one day.
+pub struct DayStats {
+    pub date: NaiveDate,
+    pub created: u64,
+    pub completed: u64,
+}
+
+/// Numbers about a task list, see [`crate::app::TodoApp::stats`].
+pub struct Stats {
+    /// The last [`DAYS`] days up to today, oldest first.
+    pub days: Vec<DayStats>,
+    pub undone: usize,
+    pub pending: usize,
+    pub done: usize,
+    /// Tasks per tag, most used first.
+    pub tags: Vec<(String, usize)>,
+    /// Average time from creating a task to completing it.
+    pub average_completion: Option<Duration>,
+    /// Days in a row, up to today or yesterday, on which a task was completed.
+    pub streak: usize,
+    /// Finished pomodoros per task description, most first.
+    pub pomodoros: Vec<(String, u32)>,
+}
+
+impl Stats {
+    pub fn of(tasks: &[Task], today: NaiveDate) -> Stats {
+        let first_day = today - Duration::days(DAYS as i64 - 1);
+        let mut days: Vec<DayStats> = (0..DAYS as i64)
+            .map(|offset| DayStats {
+                date: first_day + Duration::days(offset),
+                created: 0,
+                completed: 0,
+            })
+            .collect();
+        let day_index = |date: NaiveDate| {
+            let offset = (date - first_day).num_days();
+            usize::try_from(offset).ok().filter(|&i| i < DAYS)
+        };
+
+        let mut completion_times = Vec::new();
+        let mut completion_days = Vec::new();
+        let mut tags: HashMap<&str, usize> = HashMap::new();
+        for task in tasks {
+            if let Some(i) = task.created_at.and_then(|c| day_index(c.date_naive())) {
+                days[i].created += 1;
+            }
+            if let Some(completed_at) = task.completed_at() {
+                completion_days.push(completed_at.date_naive());
+                if let Some(i) = day_index(completed_at.date_naive()) {
+                    days[i].completed += 1;
+                }
+                if let Some(created_at) = task.created_at {
+                    completion_times.push(completed_at - created_at);
+                }
+            }
+            for tag in task.tags() {
+                *tags.entry(tag).or_default() += 1;
+            }
+        }
+
+        let mut tags: Vec<(String, usize)> = tags
+            .into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect();
+        tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let average_completion = (!completion_times.is_empty()).then(|| {
+            let total: Duration = completion_times.iter().copied().sum();
+            total / completion_times.len() as i32
+        });
+
+        // A streak is still alive when nothing was completed yet today
+        let mut day = today;
+        if !completion_days.contains(&day) {
+            day -= Duration::days(1);
+        }
+        let mut streak = 0;
+        while completion_days.contains(&day) {
+            streak += 1;
+            day -= Duration::days(1);
+        }
+
+        let mut pomodoros: Vec<(String, u32)> = tasks
+            .iter()
+            .filter(|task| task.pomodoros > 0)
+            .map(|task| (task.description.clone(), task.pomodoros))
+            .collect();
+        pomodoros.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+        let count = |status: TaskStatus| tasks.iter().filter(|t| t.status == status).count();
+        Stats {
+            days,
+            undone: count(TaskStatus::Undone),
+            pending: count(TaskStatus::Pending),
+            done: count(TaskStatus::Done),
+            tags,
+            average_completion,
+            streak,
+            pomodoros,
+        }
+    }
+
+    pub fn pomodoro_count(&self) -> u32 {
+        self.pomodoros.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn created(&self) -> Vec<u64> {
+        self.days.iter().map(|day| day.created).collect()
+    }
+
+    pub fn completed(&self) -> Vec<u64> {
+        self.days.iter().map(|day| day.completed).collect()
+    }
+}
+
+/// A duration in the largest fitting unit, e.g. `3d 4h` or `25m`.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    match (minutes / (24 * 60), minutes / 60 % 24, minutes % 60) {
+        (0, 0, minutes) => format!("{}m", minutes),
+        (0, hours, minutes) => format!("{}h {}m", hours, minutes),
+        (days, hours, _) => format!("{}d {}h", days, hours),
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Tasks: {} undone, {} pending, {} done",
+            self.undone, self.pending, self.done
+        )?;
+        let average = self
+            .average_completion
+            .map_or("-".to_string(), format_duration);
+        writeln!(f, "Average time to completion: {}", average)?;
+        writeln!(f, "Streak: {} days", self.streak)?;
+        writeln!(f, "Pomodoros: {}", self.pomodoro_count())?;
+        if !self.tags.is_empty() {
+            writeln!(f, "\nTags:")?;
+            for (tag, count) in &self.tags {
+                writeln!(f, "  @{:<16} {}", tag, count)?;
+            }
+        }
+        if !self.pomodoros.is_empty() {
+            writeln!(f, "\nPomodoros:")?;
+            for (task, count) in &self.pomodoros {
+                writeln!(f, "  {:>3}  {}", count, task)?;
+            }
+        }
+        writeln!(f, "\nLast {} days   created  completed", DAYS)?;
+        for day in &self.days {
+            writeln!(
+                f,
+                "  {}   {:>7}  {:>9}",
+                day.date.format("%Y-%m-%d"),
+                day.created,
+                day.completed
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Time tracked with the task timers, see [`crate::app::TodoApp::time_totals`].
+pub struct TimeTotals {
+    pub total: Duration,
+    /// Per task description, most time first.
+    pub tasks: Vec<(String, Duration)>,
+    /// Per tag, most time first.
+    pub tags: Vec<(String, Duration)>,
+    /// Per day with tracked time, oldest first.
+    pub days: Vec<(NaiveDate, Duration)>,
+}
+
+impl TimeTotals {
+    /// Totals with running timers counted up to `now`.
+    pub fn of(tasks: &[Task], now: DateTime<Local>) -> TimeTotals {
+        let mut tags: HashMap<&str, Duration> = HashMap::new();
+        let mut days: HashMap<NaiveDate, Duration> = HashMap::new();
+        let mut task_totals = Vec::new();
+        for task in tasks.iter().filter(|task| !task.time_log.is_empty()) {
+            let spent = task.time_spent(now);
+            task_totals.push((task.description.clone(), spent));
+            for tag in task.tags() {
+                *tags.entry(tag).or_insert_with(Duration::zero) += spent;
+            }
+            for interval in &task.time_log {
+                let end = interval.end.unwrap_or(now);
+                for (day, spent) in split_by_day(interval.start.naive_local(), end.naive_local()) {
+                    *days.entry(day).or_insert_with(Duration::zero) += spent;
+                }
+            }
+        }
+
+        task_totals.sort_by_key(|(_, spent)| std::cmp::Reverse(*spent));
+        let mut tags: Vec<(String, Duration)> = tags
+            .into_iter()
+            .map(|(tag, spent)| (tag.to_string(), spent))
+            .collect();
+        tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let mut days: Vec<(NaiveDate, Duration)> = days.into_iter().collect();
+        days.sort_by_key(|(day, _)| *day);
+
+        TimeTotals {
+            total: task_totals.iter().map(|(_, spent)| *spent).sum(),
+            tasks: task_totals,
+            tags,
+            days,
+        }
+    }
+
+    /// Time tracked on the given day.
+    pub fn on(&self, date: NaiveDate) -> Duration {
+        self.days
+            .iter()
+            .find(|(day, _)| *day == date)
+            .map_or(Duration::zero(), |(_, spent)| *spent)
+    }
+}
+
+/// Splits an interval at midnight into the time spent on each day.
+fn split_by_day(start: NaiveDateTime, end: NaiveDateTime) -> Vec<(NaiveDate, Duration)> {
+    let mut days = Vec::new();
+    let mut from = start;
+    while from < end {
+        let midnight = (from.date() + Duration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let to = midnight.min(end);
+        days.push((from.date(), to - from));
+        from = to;
+    }
+    days
+}
+
+impl fmt::Display for TimeTotals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total: {}", format_duration(self.total))?;
+        let sections = [("Tasks", &self.tasks, ""), ("Tags", &self.tags, "@")];
+        for (name, totals, prefix) in sections {
+            if !totals.is_empty() {
+                writeln!(f, "\n{}:", name)?;
+                for (what, spent) in totals {
+                    writeln!(f, "  {:>8}  {}{}", format_duration(*spent), prefix, what)?;
+                }
+            }
+        }
+        if !self.days.is_empty() {
+            writeln!(f, "\nDays:")?;
+            for (day, spent) in &self.days {
+                writeln!(
+                    f,
+                    "  {:>8}  {}",
+                    format_duration(*spent),
+                    day.format("%Y-%m-%d")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
-use chrono::{DateTime, Local};
+use crate::estimate::Estimate;
+use chrono::{DateTime, Duration, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
@@ -8,9 +9,249 @@ pub enum TaskStatus {
     Done,
 }
 
+impl TaskStatus {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            TaskStatus::Undone => "[ ]",
+            TaskStatus::Pending => "[-]",
+            TaskStatus::Done => "[x]",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaskStatus::Undone => "Undone",
+            TaskStatus::Pending => "Pending",
+            TaskStatus::Done => "Done",
+        }
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<TaskStatus> {
+        match symbol {
+            "[ ]" => Some(TaskStatus::Undone),
+            "[-]" => Some(TaskStatus::Pending),
+            "[x]" | "[X]" => Some(TaskStatus::Done),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Task {
+    /// Stable identifier, unique within the list. `0` until assigned by `TodoApp`.
+    #[serde(default)]
+    pub id: u64,
     pub description: String,
     pub status: TaskStatus,
     pub created_at: Option<DateTime<Local>>,
+    /// When the description, status or notes last changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<DateTime<Local>>,
+    /// Manual sort position; lower comes first within a status group.
+    #[serde(default)]
+    pub position: u64,
+    /// Free-form multi-line notes.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// Every status change, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<StatusChange>,
+    /// Time tracked with the timer, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_log: Vec<TimeInterval>,
+    /// Until when the task is snoozed; it is hidden from the list until then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait: Option<DateTime<Local>>,
+    /// Finished pomodoro work intervals.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pomodoros: u32,
+}
+
+fn is_zero(count: &u32) -> bool {
+    *count == 0
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StatusChange {
+    pub status: TaskStatus,
+    pub at: DateTime<Local>,
+}
+
+/// A stretch of time spent on a task. `end` is `None` while the timer runs.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TimeInterval {
+    pub start: DateTime<Local>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Local>>,
+}
+
+impl TimeInterval {
+    /// The length of the interval, counting a running one up to `now`.
+    pub fn duration(&self, now: DateTime<Local>) -> Duration {
+        self.end.unwrap_or(now) - self.start
+    }
+}
+
+impl Task {
+    pub fn new(id: u64, description: String, status: TaskStatus) -> Task {
+        Task {
+            id,
+            description,
+            status,
+            created_at: Some(Local::now()),
+            modified_at: None,
+            position: 0,
+            notes: String::new(),
+            history: Vec::new(),
+            time_log: Vec::new(),
+            wait: None,
+            pomodoros: 0,
+        }
+    }
+
+    /// Changes the status, recording the change in the history. Marking the task done
+    /// stops its timer.
+    pub fn set_status(&mut self, status: TaskStatus) {
+        if status == TaskStatus::Done {
+            self.stop_timer();
+        }
+        if self.status != status {
+            self.history.push(StatusChange {
+                status: status.clone(),
+                at: Local::now(),
+            });
+            self.status = status;
+            self.touch();
+        }
+    }
+
+    /// Records that the task was just changed.
+    pub fn touch(&mut self) {
+        self.modified_at = Some(Local::now());
+    }
+
+    /// When the task was marked done, if it is done.
+    pub fn completed_at(&self) -> Option<DateTime<Local>> {
+        if self.status != TaskStatus::Done {
+            return None;
+        }
+        self.history
+            .iter()
+            .rev()
+            .find(|change| change.status == TaskStatus::Done)
+            .map(|change| change.at)
+    }
+
+    pub fn is_snoozed(&self, now: DateTime<Local>) -> bool {
+        self.wait.is_some_and(|wait| wait > now)
+    }
+
+    /// When the running timer was started, if it runs.
+    pub fn timer_started(&self) -> Option<DateTime<Local>> {
+        self.time_log
+            .last()
+            .filter(|interval| interval.end.is_none())
+            .map(|interval| interval.start)
+    }
+
+    pub fn start_timer(&mut self) {
+        if self.timer_started().is_none() {
+            self.time_log.push(TimeInterval {
+                start: Local::now(),
+                end: None,
+            });
+        }
+    }
+
+    /// Stops the running timer, returning how long it ran.
+    pub fn stop_timer(&mut self) -> Option<Duration> {
+        let interval = self.time_log.last_mut().filter(|i| i.end.is_none())?;
+        let now = Local::now();
+        interval.end = Some(now);
+        Some(interval.duration(now))
+    }
+
+    /// All tracked time, including the running timer up to `now`.
+    pub fn time_spent(&self, now: DateTime<Local>) -> Duration {
+        self.time_log
+            .iter()
+            .map(|interval| interval.duration(now))
+            .sum()
+    }
+
+    /// URLs mentioned in the description or the notes.
+    pub fn links(&self) -> impl Iterator<Item = &str> {
+        self.description
+            .split_whitespace()
+            .chain(self.notes.split_whitespace())
+            .filter(|word| word.starts_with("http://") || word.starts_with("https://"))
+    }
+
+    /// Projects are words of the description prefixed with `+`, e.g. `+work`.
+    pub fn projects(&self) -> impl Iterator<Item = &str> {
+        self.words_with_prefix('+')
+    }
+
+    /// Tags are words of the description prefixed with `@`, e.g. `@home`.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.words_with_prefix('@')
+    }
+
+    /// Due date written as `due:YYYY-MM-DD` in the description.
+    pub fn due(&self) -> Option<NaiveDate> {
+        self.description
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix("due:"))
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+    }
+
+    /// Ids of the tasks this one depends on, written as `dep:3` or `dep:3,5` in the
+    /// description.
+    pub fn dependencies(&self) -> Vec<u64> {
+        self.description
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix("dep:"))
+            .flat_map(|ids| ids.split(','))
+            .filter_map(|id| id.trim_start_matches('#').parse().ok())
+            .collect()
+    }
+
+    /// The dependencies among `tasks` that are not done yet.
+    pub fn blockers<'a>(&self, tasks: &'a [Task]) -> Vec<&'a Task> {
+        let dependencies = self.dependencies();
+        tasks
+            .iter()
+            .filter(|task| dependencies.contains(&task.id) && task.status != TaskStatus::Done)
+            .collect()
+    }
+
+    /// Expected effort written as `est:2h` or `est:3pts` in the description.
+    pub fn estimate(&self) -> Option<Estimate> {
+        self.description
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix("est:"))
+            .and_then(Estimate::parse)
+    }
+
+    /// The day the task is planned for: its due date, or else the day it was created.
+    pub fn planned_day(&self) -> Option<NaiveDate> {
+        self.due()
+            .or_else(|| self.created_at.map(|created| created.date_naive()))
+    }
+
+    /// Priority written as a `(A)` prefix of the description.
+    pub fn priority(&self) -> Option<char> {
+        let mut chars = self.description.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some('('), Some(p), Some(')')) if p.is_ascii_uppercase() => Some(p),
+            _ => None,
+        }
+    }
+
+    fn words_with_prefix(&self, prefix: char) -> impl Iterator<Item = &str> {
+        self.description
+            .split_whitespace()
+            .filter_map(move |word| word.strip_prefix(prefix))
+            .filter(|word| !word.is_empty())
+    }
 }
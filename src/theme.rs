@@ -0,0 +1,260 @@
+use serde::Deserialize;
+use std::{collections::HashMap, env};
+use tui::style::{Color, Modifier, Style};
+
+/// Styles of every part of the TUI.
+pub struct Theme {
+    pub undone: Style,
+    pub pending: Style,
+    pub done: Style,
+    pub blocked: Style,
+    pub highlight: Style,
+    pub selected: Style,
+    pub title: Style,
+    pub border: Style,
+    pub input: Style,
+    pub status: Style,
+    pub tab: Style,
+    pub tab_highlight: Style,
+}
+
+/// A theme as written in the config: every style is optional and falls back to the
+/// `base` theme. Styles are words like `"bold red on black"` or `"#859900 crossed_out"`.
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeSpec {
+    pub base: Option<String>,
+    pub undone: Option<String>,
+    pub pending: Option<String>,
+    pub done: Option<String>,
+    pub blocked: Option<String>,
+    pub highlight: Option<String>,
+    pub selected: Option<String>,
+    pub title: Option<String>,
+    pub border: Option<String>,
+    pub input: Option<String>,
+    pub status: Option<String>,
+    pub tab: Option<String>,
+    pub tab_highlight: Option<String>,
+}
+
+const DARK: &str = r##"
+undone = "red"
+pending = "yellow"
+done = "green crossed_out"
+blocked = "dark_gray"
+highlight = "bold"
+selected = "on dark_gray"
+title = "bold"
+border = ""
+input = "yellow"
+status = "green"
+tab = ""
+tab_highlight = "bold reversed"
+"##;
+
+const LIGHT: &str = r##"
+undone = "red"
+pending = "magenta"
+done = "dark_gray crossed_out"
+blocked = "gray"
+highlight = "bold"
+selected = "on gray"
+title = "bold"
+border = "dark_gray"
+input = "blue"
+status = "blue"
+tab = "dark_gray"
+tab_highlight = "bold reversed"
+"##;
+
+const SOLARIZED: &str = r##"
+undone = "#dc322f"
+pending = "#b58900"
+done = "#859900 crossed_out"
+blocked = "#586e75"
+highlight = "bold on #073642"
+selected = "on #586e75"
+title = "bold #93a1a1"
+border = "#586e75"
+input = "#268bd2"
+status = "#2aa198"
+tab = "#839496"
+tab_highlight = "bold #fdf6e3 on #268bd2"
+"##;
+
+const HIGH_CONTRAST: &str = r##"
+undone = "bold light_red"
+pending = "bold light_yellow"
+done = "light_green crossed_out"
+blocked = "bold light_magenta"
+highlight = "bold black on white"
+selected = "black on light_yellow"
+title = "bold white"
+border = "white"
+input = "bold white"
+status = "bold light_cyan"
+tab = "white"
+tab_highlight = "bold black on white"
+"##;
+
+const MONOCHROME: &str = r##"
+undone = ""
+pending = "italic"
+done = "dim crossed_out"
+blocked = "dim"
+highlight = "reversed"
+selected = "underlined"
+title = "bold"
+border = ""
+input = ""
+status = ""
+tab = ""
+tab_highlight = "reversed"
+"##;
+
+pub const BUILTIN_THEMES: [&str; 5] = ["dark", "light", "solarized", "high-contrast", "monochrome"];
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::load(&default_theme_name(), &HashMap::new()).expect("built-in themes are valid")
+    }
+}
+
+/// `monochrome` when `NO_COLOR` is set (see https://no-color.org), `dark` otherwise.
+pub fn default_theme_name() -> String {
+    match env::var("NO_COLOR") {
+        Ok(value) if !value.is_empty() => "monochrome".to_string(),
+        _ => "dark".to_string(),
+    }
+}
+
+impl Theme {
+    /// Resolves a built-in or user-defined theme by name.
+    pub fn load(name: &str, themes: &HashMap<String, ThemeSpec>) -> Result<Theme, String> {
+        let spec = resolve_spec(name, themes, 0)?;
+        let style = |field: &Option<String>| parse_style(field.as_deref().unwrap_or(""));
+        Ok(Theme {
+            undone: style(&spec.undone)?,
+            pending: style(&spec.pending)?,
+            done: style(&spec.done)?,
+            blocked: style(&spec.blocked)?,
+            highlight: style(&spec.highlight)?,
+            selected: style(&spec.selected)?,
+            title: style(&spec.title)?,
+            border: style(&spec.border)?,
+            input: style(&spec.input)?,
+            status: style(&spec.status)?,
+            tab: style(&spec.tab)?,
+            tab_highlight: style(&spec.tab_highlight)?,
+        })
+    }
+}
+
+fn resolve_spec(
+    name: &str,
+    themes: &HashMap<String, ThemeSpec>,
+    depth: usize,
+) -> Result<ThemeSpec, String> {
+    if depth > 8 {
+        return Err(format!("Theme '{}' inherits from itself", name));
+    }
+    if let Some(spec) = themes.get(name) {
+        let base = resolve_spec(spec.base.as_deref().unwrap_or("dark"), themes, depth + 1)?;
+        return Ok(ThemeSpec {
+            base: None,
+            undone: spec.undone.clone().or(base.undone),
+            pending: spec.pending.clone().or(base.pending),
+            done: spec.done.clone().or(base.done),
+            blocked: spec.blocked.clone().or(base.blocked),
+            highlight: spec.highlight.clone().or(base.highlight),
+            selected: spec.selected.clone().or(base.selected),
+            title: spec.title.clone().or(base.title),
+            border: spec.border.clone().or(base.border),
+            input: spec.input.clone().or(base.input),
+            status: spec.status.clone().or(base.status),
+            tab: spec.tab.clone().or(base.tab),
+            tab_highlight: spec.tab_highlight.clone().or(base.tab_highlight),
+        });
+    }
+    let builtin = match name {
+        "dark" => DARK,
+        "light" => LIGHT,
+        "solarized" => SOLARIZED,
+        "high-contrast" => HIGH_CONTRAST,
+        "monochrome" => MONOCHROME,
+        _ => {
+            return Err(format!(
+                "Unknown theme '{}' (built-in themes: {})",
+                name,
+                BUILTIN_THEMES.join(", ")
+            ))
+        }
+    };
+    Ok(toml::from_str(builtin).expect("built-in themes are valid"))
+}
+
+/// Parses a style such as `"bold red on black"`: modifiers, a foreground color and an
+/// optional background color after `on`.
+fn parse_style(spec: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    let mut words = spec.split_whitespace();
+    while let Some(word) = words.next() {
+        let modifier = match word.to_lowercase().as_str() {
+            "bold" => Modifier::BOLD,
+            "dim" => Modifier::DIM,
+            "italic" => Modifier::ITALIC,
+            "underlined" => Modifier::UNDERLINED,
+            "reversed" => Modifier::REVERSED,
+            "crossed_out" => Modifier::CROSSED_OUT,
+            "on" => {
+                let color = words
+                    .next()
+                    .ok_or_else(|| format!("Missing background color in style '{}'", spec))?;
+                style = style.bg(parse_color(color)?);
+                continue;
+            }
+            _ => {
+                style = style.fg(parse_color(word)?);
+                continue;
+            }
+        };
+        style = style.add_modifier(modifier);
+    }
+    Ok(style)
+}
+
+/// Parses a color name like `light_blue`, a hex color like `#268bd2` or a 256-color index.
+fn parse_color(spec: &str) -> Result<Color, String> {
+    let color = match spec.to_lowercase().replace('-', "_").as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "dark_gray" | "dark_grey" => Color::DarkGray,
+        "light_red" => Color::LightRed,
+        "light_green" => Color::LightGreen,
+        "light_yellow" => Color::LightYellow,
+        "light_blue" => Color::LightBlue,
+        "light_magenta" => Color::LightMagenta,
+        "light_cyan" => Color::LightCyan,
+        "white" => Color::White,
+        hex if hex.starts_with('#') && hex.len() == 7 && hex.is_ascii() => {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+            match (channel(1), channel(3), channel(5)) {
+                (Ok(r), Ok(g), Ok(b)) => Color::Rgb(r, g, b),
+                _ => return Err(format!("Invalid color '{}'", spec)),
+            }
+        }
+        index => match index.parse() {
+            Ok(index) => Color::Indexed(index),
+            Err(_) => return Err(format!("Unknown color '{}'", spec)),
+        },
+    };
+    Ok(color)
+}
@@ -1,110 +1,1009 @@
 use crate::app::TodoApp;
-use crate::task::TaskStatus;
+use crate::config::SavedView;
+use crate::editor::LineEditor;
+use crate::keymap::{Action, Keymap};
+use crate::pomodoro::{Phase, Pomodoro};
+use crate::preferences::Preferences;
+use crate::query::{self, Query};
+use crate::report::Format;
+use crate::sort::{Sort, SortKey};
+use crate::stats;
+use crate::task::{Task, TaskStatus};
+use crate::theme::Theme;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
+use std::collections::HashSet;
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{
+        Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Sparkline, Tabs, Wrap,
+    },
     Frame,
 };
+use unicode_width::UnicodeWidthStr;
 
 pub enum InputMode {
     View,
     Add,
     Edit,
     Filter,
+    SaveView,
+    /// The menu choosing the sort order of the list.
+    Sort,
+    /// The board with a column per status; `row` is the highlighted task of `column`.
+    Board {
+        column: usize,
+        row: usize,
+    },
+    /// The agenda of a week, `week` weeks from the current one, with `day` (0 is Monday)
+    /// highlighted.
+    Agenda {
+        week: i64,
+        day: usize,
+    },
+    /// Charts and numbers about the whole list.
+    Stats,
+    /// The report of the last `days` days, scrolled down by `scroll` lines.
+    Report {
+        days: i64,
+        scroll: u16,
+    },
+    /// Choices of how long to snooze the selected tasks.
+    Snooze,
+    /// Prompts for the date to snooze the selected tasks until.
+    SnoozeUntil,
+    /// Prompts for a tag, priority or project applied to the selected tasks.
+    Tag,
+    Prioritize,
+    Move,
+    /// The help overlay, scrolled down by the given number of lines.
+    Help(u16),
 }
 
+/// Saved views and the ad-hoc `/` filter that narrow down the task list.
+pub struct Filters {
+    pub views: Vec<SavedView>,
+    pub active_view: Option<usize>,
+    pub filter: String,
+}
+
+impl Filters {
+    /// The query of the active view combined with the ad-hoc filter.
+    pub fn query(&self) -> String {
+        self.query_with(&self.filter)
+    }
+
+    /// The name the active list is remembered by, e.g. for its sort order.
+    pub fn list_name(&self) -> &str {
+        match self.active_view.and_then(|i| self.views.get(i)) {
+            Some(view) => &view.name,
+            None => "All",
+        }
+    }
+
+    /// Like [`Filters::query`], with `filter` in place of the ad-hoc filter.
+    pub fn query_with(&self, filter: &str) -> String {
+        match self.active_view.and_then(|i| self.views.get(i)) {
+            Some(view) if filter.is_empty() => view.query.clone(),
+            Some(view) => format!("({}) and ({})", view.query, filter),
+            None => filter.to_string(),
+        }
+    }
+}
+
+/// The statuses shown as columns of the board, left to right.
+pub const BOARD_COLUMNS: [TaskStatus; 3] =
+    [TaskStatus::Undone, TaskStatus::Pending, TaskStatus::Done];
+
+/// The tasks matching `query` shown in a column of the board.
+pub fn board_column(app: &TodoApp, query: &str, column: usize) -> Vec<Task> {
+    app.filter_tasks(query)
+        .into_iter()
+        .filter(|task| task.status == BOARD_COLUMNS[column])
+        .collect()
+}
+
+/// The Monday of the week `week` weeks from the current one.
+pub fn agenda_monday(week: i64) -> NaiveDate {
+    let today = Local::now().date_naive();
+    let weekday = today.weekday().num_days_from_monday() as i64;
+    today - Duration::days(weekday) + Duration::weeks(week)
+}
+
+/// The choices of the snooze menu: the key, a name and until when.
+pub fn snooze_choices(now: DateTime<Local>) -> [(char, &'static str, DateTime<Local>); 3] {
+    let tomorrow = now.date_naive() + Duration::days(1);
+    [
+        ('h', "for an hour", now + Duration::hours(1)),
+        ('t', "until tomorrow", query::start_of_day(tomorrow)),
+        (
+            'w',
+            "until next week",
+            query::start_of_day(agenda_monday(1)),
+        ),
+    ]
+}
+
+/// The start of a report covering the last `days` days.
+pub fn report_since(days: i64) -> DateTime<Local> {
+    Local::now() - Duration::days(days)
+}
+
+/// Tasks picked for a bulk operation: marked ones plus the range of visual mode.
+#[derive(Default)]
+pub struct Selection {
+    pub marked: HashSet<u64>,
+    /// Index in the shown list where visual mode started.
+    pub anchor: Option<usize>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.marked.is_empty() && self.anchor.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.marked.clear();
+        self.anchor = None;
+    }
+
+    /// Ids of the selected tasks among `tasks`, in list order, with the visual range
+    /// reaching from the anchor to `current`.
+    pub fn ids(&self, tasks: &[Task], current: usize) -> Vec<u64> {
+        let range = self
+            .anchor
+            .map(|anchor| anchor.min(current)..=anchor.max(current));
+        tasks
+            .iter()
+            .enumerate()
+            .filter(|(i, task)| {
+                self.marked.contains(&task.id) || range.as_ref().is_some_and(|r| r.contains(i))
+            })
+            .map(|(_, task)| task.id)
+            .collect()
+    }
+}
+
+/// Draws the whole TUI and returns the area of the task list inside its border, and how
+/// many lines the overlays drawn over the list and its panes (help, report) show.
+#[allow(clippy::too_many_arguments)]
 pub fn ui<B: Backend>(
     f: &mut Frame<B>,
     app: &TodoApp,
     state: &mut ListState,
-    filter: &str,
-    input: &str,
+    filters: &Filters,
+    selection: &Selection,
+    input: &LineEditor,
     input_mode: &InputMode,
     status_message: &Option<String>,
-) {
+    keymap: &Keymap,
+    theme: &Theme,
+    preferences: &Preferences,
+    pomodoro: Option<&Pomodoro>,
+) -> (Rect, u16) {
+    let views = &filters.views;
+    // While typing a filter the list follows the input
+    let filter = match input_mode {
+        InputMode::Filter => filters.query_with(input.text()),
+        _ => filters.query(),
+    };
+    let tabs_height = if views.is_empty() { 0 } else { 3 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Min(5),    // Area for tasks list
-                Constraint::Length(3), // Area for input at the bottom
-                Constraint::Length(3), // Status message at the bottom
+                Constraint::Length(tabs_height), // Saved views at the top
+                Constraint::Min(5),              // Area for tasks list
+                Constraint::Length(3),           // Area for input at the bottom
+                Constraint::Length(3),           // Status message at the bottom
             ]
             .as_ref(),
         )
         .split(f.size());
 
-    // Use a consistent color scheme
-    let undone_color = Color::Red;
-    let pending_color = Color::Yellow;
-    let done_color = Color::Green;
+    // Render saved views as tabs, each with its own completion percentage
+    if !views.is_empty() {
+        let all = format!("0 All {:.0}%", app.completion_percentage());
+        let titles: Vec<Spans> = std::iter::once(Spans::from(all))
+            .chain(views.iter().enumerate().map(|(i, view)| {
+                let percentage = app.completion_percentage_of(&app.filter_tasks(&view.query));
+                Spans::from(format!("{} {} {:.0}%", i + 1, view.name, percentage))
+            }))
+            .collect();
+        let tabs = Tabs::new(titles)
+            .select(filters.active_view.map_or(0, |i| i + 1))
+            .block(block("Views (Alt-N or N Tab)", theme))
+            .style(theme.tab)
+            .highlight_style(theme.tab_highlight);
+        f.render_widget(tabs, chunks[0]);
+    }
 
     // Render tasks list
-    let tasks: Vec<ListItem> = app
-        .filter_tasks(filter)
+    let filtered_tasks = app.filter_tasks(&filter);
+    let selected_ids = selection.ids(&filtered_tasks, state.selected().unwrap_or(0));
+    let tasks: Vec<ListItem> = filtered_tasks
         .iter()
         .map(|task| {
-            let (symbol, style) = match task.status {
-                TaskStatus::Undone => ("[ ]", Style::default().fg(undone_color)),
-                TaskStatus::Pending => ("[-]", Style::default().fg(pending_color)),
-                TaskStatus::Done => (
-                    "[x]",
-                    Style::default()
-                        .fg(done_color)
-                        .add_modifier(Modifier::CROSSED_OUT),
-                ),
-            };
-            let content = Spans::from(vec![Span::styled(
-                format!("{} {}", symbol, task.description),
+            let blockers = task.blockers(&app.tasks);
+            let mut style = status_style(&task.status, theme);
+            if !blockers.is_empty() {
+                style = style.patch(theme.blocked);
+            }
+            if selected_ids.contains(&task.id) {
+                style = style.patch(theme.selected);
+            }
+            let mut spans = vec![Span::styled(
+                format!("{} {}", task.status.symbol(), task.description),
                 style,
-            )]);
-            ListItem::new(content)
+            )];
+            if !task.notes.is_empty() {
+                spans.push(Span::styled(" ✎", theme.title));
+            }
+            if !blockers.is_empty() {
+                let ids: Vec<String> = blockers.iter().map(|t| format!("#{}", t.id)).collect();
+                spans.push(Span::styled(
+                    format!(" (blocked by {})", ids.join(", ")),
+                    theme.blocked,
+                ));
+            }
+            ListItem::new(Spans::from(spans))
         })
         .collect();
 
     let completion_percentage = app.completion_percentage();
+    let filter_indicator = if filters.filter.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " [Filter: {} | {}]",
+            filters.filter,
+            key_hints(keymap, &[Action::ClearFilter])
+        )
+    };
+    let snoozed_indicator = match app.snoozed_count(Local::now()) {
+        0 => String::new(),
+        count => format!(" [{} snoozed]", count),
+    };
+    let sort_indicator = if app.sort == Sort::default() {
+        String::new()
+    } else {
+        format!(" [Sort: {}]", app.sort)
+    };
+    // What is left to do of the shown tasks, per status
+    let undone_estimate = TodoApp::remaining_estimate(&filtered_tasks, TaskStatus::Undone);
+    let pending_estimate = TodoApp::remaining_estimate(&filtered_tasks, TaskStatus::Pending);
+    let estimate_indicator = match (undone_estimate.is_zero(), pending_estimate.is_zero()) {
+        (true, true) => String::new(),
+        (false, true) => format!(", {} left", undone_estimate),
+        (true, false) => format!(", {} left (pending)", pending_estimate),
+        (false, false) => format!(", {} left + {} pending", undone_estimate, pending_estimate),
+    };
     let title = format!(
-        "Todo List (d: delete, D: remove done, Space: toggle) {:.1}% Complete",
-        completion_percentage
+        "Todo List{}{}{} ({}) {:.1}% Complete{}",
+        filter_indicator,
+        sort_indicator,
+        snoozed_indicator,
+        key_hints(
+            keymap,
+            &[
+                Action::Delete,
+                Action::RemoveDone,
+                Action::Toggle,
+                Action::Help
+            ]
+        ),
+        completion_percentage,
+        estimate_indicator
     );
     let tasks_list = List::new(tasks)
-        .block(Block::default().borders(Borders::ALL).title(Span::styled(
-            title,
-            Style::default().add_modifier(Modifier::BOLD),
-        )))
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .block(block(title, theme))
+        .highlight_style(theme.highlight)
         .highlight_symbol("> ");
 
-    f.render_stateful_widget(tasks_list, chunks[0], state);
+    // Show details of the highlighted task beside the list, or only its notes below it
+    let selected_task = state.selected().and_then(|i| filtered_tasks.get(i));
+    let selected_notes = selected_task
+        .map(|task| task.notes.as_str())
+        .filter(|notes| !notes.is_empty());
+    let (list_area, side_area) = if preferences.show_details {
+        let (direction, constraints) = if chunks[1].width >= 100 {
+            (
+                Direction::Horizontal,
+                [Constraint::Percentage(60), Constraint::Percentage(40)],
+            )
+        } else {
+            (
+                Direction::Vertical,
+                [Constraint::Min(3), Constraint::Percentage(50)],
+            )
+        };
+        let areas = Layout::default()
+            .direction(direction)
+            .constraints(constraints.as_ref())
+            .split(chunks[1]);
+        (areas[0], Some(areas[1]))
+    } else if let Some(notes) = selected_notes {
+        let height = (notes.lines().count() as u16 + 2).min(chunks[1].height / 2);
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(height)].as_ref())
+            .split(chunks[1]);
+        (areas[0], Some(areas[1]))
+    } else {
+        (chunks[1], None)
+    };
+
+    f.render_stateful_widget(tasks_list, list_area, state);
+
+    if let Some(area) = side_area {
+        let side_widget = if preferences.show_details {
+            Paragraph::new(
+                selected_task
+                    .map(|task| detail_lines(task, &app.tasks))
+                    .unwrap_or_default(),
+            )
+            .block(block("Details", theme))
+        } else {
+            Paragraph::new(selected_notes.unwrap_or_default()).block(block("Notes", theme))
+        };
+        f.render_widget(side_widget.wrap(Wrap { trim: false }), area);
+    }
 
     // Render input box at the bottom for adding a new task, editing, or filtering
+    let label = match input_mode {
+        InputMode::Add => "New Task: ",
+        InputMode::Filter => "Filter: ",
+        InputMode::Edit => "Edit Task: ",
+        InputMode::SaveView => "View Name: ",
+        InputMode::Tag => "Tag: ",
+        InputMode::Prioritize => "Priority (A-Z, empty to clear): ",
+        InputMode::Move => "Project: ",
+        InputMode::SnoozeUntil => "Snooze until (YYYY-MM-DD, 3d, 12h, tomorrow): ",
+        InputMode::View
+        | InputMode::Sort
+        | InputMode::Snooze
+        | InputMode::Board { .. }
+        | InputMode::Agenda { .. }
+        | InputMode::Stats
+        | InputMode::Report { .. }
+        | InputMode::Help(_) => "",
+    };
     let input_text = match input_mode {
-        InputMode::Add => format!("New Task: {}", input),
-        InputMode::Filter => format!("Filter: {}", input),
-        InputMode::Edit => format!("Edit Task: {}", input),
-        InputMode::View => "".to_string(),
+        InputMode::View
+        | InputMode::Sort
+        | InputMode::Snooze
+        | InputMode::Board { .. }
+        | InputMode::Agenda { .. }
+        | InputMode::Stats
+        | InputMode::Report { .. }
+        | InputMode::Help(_) => String::new(),
+        _ => format!("{}{}", label, input.text()),
     };
 
+    let input_title = match input_mode {
+        InputMode::Filter => match Query::parse(input.text()) {
+            Ok(_) => format!("Input ({} matching)", filtered_tasks.len()),
+            Err(err) => format!("Input ({})", err),
+        },
+        _ => "Input".to_string(),
+    };
+    // Scroll long input horizontally so the cursor stays visible
+    let input_area = chunks[2];
+    let inner_width = input_area.width.saturating_sub(2) as usize;
+    let cursor_column = label.width() + input.cursor_width();
+    let scroll = (cursor_column + 1).saturating_sub(inner_width);
     let input_box = Paragraph::new(input_text)
-        .style(Style::default().fg(Color::Yellow))
-        .block(Block::default().borders(Borders::ALL).title("Input"));
+        .style(theme.input)
+        .block(block(input_title, theme))
+        .scroll((0, scroll as u16));
 
-    f.render_widget(input_box, chunks[1]);
+    f.render_widget(input_box, input_area);
+    if !label.is_empty() {
+        f.set_cursor(
+            input_area.x + 1 + (cursor_column - scroll) as u16,
+            input_area.y + 1,
+        );
+    }
 
-    // Render the status message if it exists
+    // Render the status message if it exists, with the size of the selection in the title
+    // and the running timer
+    let mut status_title = match (selection.anchor, selected_ids.len()) {
+        (Some(_), count) => format!("Status (VISUAL, {} selected)", count),
+        (None, 0) => "Status".to_string(),
+        (None, count) => format!("Status ({} selected)", count),
+    };
+    if let Some(task) = app.running_timer() {
+        let started = task.timer_started().unwrap_or_else(Local::now);
+        let seconds = (Local::now() - started).num_seconds().max(0);
+        status_title += &format!(
+            " ⏱ {}:{:02}:{:02} {}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+            task.description
+        );
+    }
+    // A running pomodoro takes the right part of the status area
+    let status_area = match pomodoro {
+        Some(pomodoro) => {
+            let areas = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(10), Constraint::Length(32)].as_ref())
+                .split(chunks[3]);
+            render_pomodoro(f, areas[1], app, pomodoro, theme);
+            areas[0]
+        }
+        None => chunks[3],
+    };
     if let Some(message) = status_message {
         let status_widget = Paragraph::new(message.as_ref())
-            .style(Style::default().fg(Color::Green))
-            .block(Block::default().borders(Borders::ALL).title("Status"));
+            .style(theme.status)
+            .block(block(status_title, theme));
 
-        f.render_widget(status_widget, chunks[2]); // Render status message at the bottom
+        f.render_widget(status_widget, status_area); // Render status message at the bottom
     } else {
         // Render an empty status message area when there is no message
-        let empty_status =
-            Paragraph::new("").block(Block::default().borders(Borders::ALL).title("Status"));
-        f.render_widget(empty_status, chunks[2]);
+        let empty_status = Paragraph::new("").block(block(status_title, theme));
+        f.render_widget(empty_status, status_area);
+    }
+
+    match input_mode {
+        InputMode::Help(scroll) => render_help(f, chunks[1], *scroll, keymap, theme),
+        InputMode::Sort => render_sort_menu(f, chunks[1], app.sort, filters.list_name(), theme),
+        InputMode::Snooze => render_snooze_menu(f, chunks[1], theme),
+        InputMode::Board { column, row } => {
+            render_board(f, chunks[1], app, &filter, *column, *row, theme)
+        }
+        InputMode::Agenda { week, day } => {
+            render_agenda(f, chunks[1], app, &filter, *week, *day, theme)
+        }
+        InputMode::Stats => render_stats(f, chunks[1], app, theme),
+        InputMode::Report { days, scroll } => {
+            render_report(f, chunks[1], app, *days, *scroll, theme)
+        }
+        _ => {}
+    }
+
+    (
+        Block::default().borders(Borders::ALL).inner(list_area),
+        chunks[1].height.saturating_sub(2),
+    )
+}
+
+/// Everything known about a task, for the detail pane.
+fn detail_lines(task: &Task, tasks: &[Task]) -> Vec<Spans<'static>> {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let field = |name: &str, value: String| {
+        Spans::from(vec![
+            Span::styled(format!("{}: ", name), bold),
+            Span::raw(value),
+        ])
+    };
+    let list = |items: Vec<&str>| items.join(", ");
+
+    let mut lines = vec![
+        Spans::from(Span::styled(task.description.clone(), bold)),
+        Spans::from(""),
+        field("ID", format!("#{}", task.id)),
+        field("Status", task.status.name().to_string()),
+    ];
+    if let Some(created_at) = task.created_at {
+        lines.push(field(
+            "Created",
+            created_at.format("%Y-%m-%d %H:%M").to_string(),
+        ));
+    }
+    if let Some(modified_at) = task.modified_at {
+        lines.push(field(
+            "Modified",
+            modified_at.format("%Y-%m-%d %H:%M").to_string(),
+        ));
+    }
+    if let Some(due) = task.due() {
+        lines.push(field("Due", due.format("%Y-%m-%d").to_string()));
+    }
+    if let Some(priority) = task.priority() {
+        lines.push(field("Priority", priority.to_string()));
+    }
+    if let Some(wait) = task.wait {
+        lines.push(field(
+            "Snoozed until",
+            wait.format("%Y-%m-%d %H:%M").to_string(),
+        ));
+    }
+    if let Some(estimate) = task.estimate() {
+        lines.push(field("Estimate", estimate.to_string()));
+    }
+    let projects: Vec<&str> = task.projects().collect();
+    if !projects.is_empty() {
+        lines.push(field("Projects", list(projects)));
+    }
+    let tags: Vec<&str> = task.tags().collect();
+    if !tags.is_empty() {
+        lines.push(field("Tags", list(tags)));
+    }
+    let dependencies = task.dependencies();
+    if !dependencies.is_empty() {
+        lines.push(Spans::from(Span::styled("Depends on:", bold)));
+        lines.extend(dependencies.iter().map(|id| {
+            let dependency = tasks.iter().find(|t| t.id == *id);
+            Spans::from(match dependency {
+                Some(t) => format!("  #{} {} {}", id, t.status.symbol(), t.description),
+                None => format!("  #{} (no such task)", id),
+            })
+        }));
+    }
+    let links: Vec<&str> = task.links().collect();
+    if !links.is_empty() {
+        lines.push(Spans::from(Span::styled("Links:", bold)));
+        lines.extend(
+            links
+                .into_iter()
+                .map(|link| Spans::from(format!("  {}", link))),
+        );
+    }
+    if !task.time_log.is_empty() {
+        let running = if task.timer_started().is_some() {
+            " (running)"
+        } else {
+            ""
+        };
+        let spent = stats::format_duration(task.time_spent(Local::now()));
+        lines.push(field("Time spent", format!("{}{}", spent, running)));
+    }
+    if task.pomodoros > 0 {
+        lines.push(field("Pomodoros", task.pomodoros.to_string()));
+    }
+    if !task.history.is_empty() {
+        lines.push(Spans::from(Span::styled("History:", bold)));
+        lines.extend(task.history.iter().map(|change| {
+            Spans::from(format!(
+                "  {}  {}",
+                change.at.format("%Y-%m-%d %H:%M"),
+                change.status.name()
+            ))
+        }));
+    }
+    if !task.notes.is_empty() {
+        lines.push(Spans::from(""));
+        lines.push(Spans::from(Span::styled("Notes:", bold)));
+        lines.extend(task.notes.lines().map(|line| Spans::from(line.to_string())));
+    }
+    lines
+}
+
+/// Lines of the help overlay, generated from the active key bindings.
+pub fn help_lines(keymap: &Keymap) -> Vec<Spans<'static>> {
+    let mut lines = Vec::new();
+    for (section, bindings) in keymap.help_sections() {
+        if !lines.is_empty() {
+            lines.push(Spans::from(""));
+        }
+        lines.push(Spans::from(Span::styled(
+            section,
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        for (keys, description) in bindings {
+            lines.push(Spans::from(format!("  {:<14} {}", keys, description)));
+        }
+    }
+    lines
+}
+
+/// How far text of `lines` lines can scroll when `height` of them fit, keeping the last
+/// line at the bottom instead of scrolling past it.
+pub fn max_scroll(lines: usize, height: u16) -> u16 {
+    (lines as u16).saturating_sub(height)
+}
+
+fn render_help<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    scroll: u16,
+    keymap: &Keymap,
+    theme: &Theme,
+) {
+    let area = centered_rect(area, 60, 100);
+    let lines = help_lines(keymap);
+    let max_scroll = max_scroll(lines.len(), area.height.saturating_sub(2));
+    let help = Paragraph::new(lines)
+        .block(block("Help (j/k: scroll, Esc: close)", theme))
+        .scroll((scroll.min(max_scroll), 0));
+    f.render_widget(Clear, area);
+    f.render_widget(help, area);
+}
+
+fn render_board<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    app: &TodoApp,
+    query: &str,
+    column: usize,
+    row: usize,
+    theme: &Theme,
+) {
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 3); 3].as_ref())
+        .split(area);
+    f.render_widget(Clear, area);
+    for (index, status) in BOARD_COLUMNS.iter().enumerate() {
+        let tasks = board_column(app, query, index);
+        let items: Vec<ListItem> = tasks
+            .iter()
+            .map(|task| {
+                let mut spans = vec![Span::styled(
+                    task.description.clone(),
+                    status_style(status, theme),
+                )];
+                if !task.notes.is_empty() {
+                    spans.push(Span::styled(" ✎", theme.title));
+                }
+                ListItem::new(Spans::from(spans))
+            })
+            .collect();
+        let title = format!("{} ({})", status.name(), tasks.len());
+        let mut column_block = block(title, theme);
+        let mut state = ListState::default();
+        if index == column {
+            column_block = column_block.border_style(theme.tab_highlight);
+            state.select(Some(row.min(tasks.len().saturating_sub(1))));
+        }
+        let list = List::new(items)
+            .block(column_block)
+            .highlight_style(theme.highlight)
+            .highlight_symbol("> ");
+        f.render_stateful_widget(list, areas[index], &mut state);
+    }
+}
+
+fn render_agenda<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    app: &TodoApp,
+    query: &str,
+    week: i64,
+    day: usize,
+    theme: &Theme,
+) {
+    let today = Local::now().date_naive();
+    let monday = agenda_monday(week);
+    let tasks = app.filter_tasks(query);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+
+    let mut lines = Vec::new();
+    let mut selected_line = 0;
+    for offset in 0..7 {
+        let date = monday + Duration::days(offset);
+        let day_tasks: Vec<&Task> = tasks
+            .iter()
+            .filter(|task| task.planned_day() == Some(date))
+            .collect();
+        let overdue = day_tasks
+            .iter()
+            .filter(|task| task.status != TaskStatus::Done && task.due().is_some_and(|d| d < today))
+            .count();
+
+        let (marker, style) = if offset as usize == day {
+            selected_line = lines.len();
+            ("> ", theme.highlight.patch(bold))
+        } else {
+            ("  ", bold)
+        };
+        let mut header = vec![Span::styled(
+            format!(
+                "{}{}{} ({})",
+                marker,
+                date.format("%a %Y-%m-%d"),
+                if date == today { " today" } else { "" },
+                day_tasks.len()
+            ),
+            style,
+        )];
+        if overdue > 0 {
+            header.push(Span::styled(
+                format!("  ! {} overdue", overdue),
+                theme.undone,
+            ));
+        }
+        lines.push(Spans::from(header));
+        lines.extend(day_tasks.iter().map(|task| {
+            Spans::from(Span::styled(
+                format!("    {} {}", task.status.symbol(), task.description),
+                status_style(&task.status, theme),
+            ))
+        }));
     }
+
+    // Keep the highlighted day in the upper half
+    let height = area.height.saturating_sub(2) as usize;
+    let max_scroll = lines.len().saturating_sub(height);
+    let scroll = selected_line.saturating_sub(height / 2).min(max_scroll);
+    let title = format!(
+        "Agenda, week of {} (h/l: week, j/k: day, t: today, Enter: show day, Esc: close)",
+        monday.format("%Y-%m-%d")
+    );
+    let agenda = Paragraph::new(lines)
+        .block(block(title, theme))
+        .scroll((scroll as u16, 0));
+    f.render_widget(Clear, area);
+    f.render_widget(agenda, area);
+}
+
+/// A countdown gauge of the current pomodoro interval, reversed for a moment when one ends.
+fn render_pomodoro<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    app: &TodoApp,
+    pomodoro: &Pomodoro,
+    theme: &Theme,
+) {
+    let (phase, style) = match pomodoro.phase {
+        Phase::Work => ("Work", theme.pending),
+        Phase::Break => ("Break", theme.done),
+    };
+    let style = style.remove_modifier(Modifier::CROSSED_OUT);
+    let style = if pomodoro.flashing() {
+        style.add_modifier(Modifier::REVERSED)
+    } else {
+        style
+    };
+    let count = app
+        .tasks
+        .iter()
+        .find(|task| task.id == pomodoro.task_id)
+        .map_or(0, |task| task.pomodoros);
+    let seconds = pomodoro.remaining().as_secs();
+    let gauge = Gauge::default()
+        .block(block(format!("{} #{}", phase, count + 1), theme))
+        .gauge_style(style)
+        .ratio(pomodoro.ratio())
+        .label(format!("{}:{:02}", seconds / 60, seconds % 60));
+    f.render_widget(gauge, area);
+}
+
+fn render_report<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    app: &TodoApp,
+    days: i64,
+    scroll: u16,
+    theme: &Theme,
+) {
+    let text = app.report(report_since(days)).render(Format::Text);
+    let lines: Vec<Spans> = text.lines().map(Spans::from).collect();
+    let max_scroll = max_scroll(lines.len(), area.height.saturating_sub(2));
+    let title = format!(
+        "Report of the last {} (h/l: period, s: save as Markdown, Esc: close)",
+        if days == 1 {
+            "day".to_string()
+        } else {
+            format!("{} days", days)
+        }
+    );
+    let report = Paragraph::new(lines)
+        .block(block(title, theme))
+        .scroll((scroll.min(max_scroll), 0));
+    f.render_widget(Clear, area);
+    f.render_widget(report, area);
+}
+
+fn render_stats<B: Backend>(f: &mut Frame<B>, area: Rect, app: &TodoApp, theme: &Theme) {
+    let stats = app.stats();
+    let time = app.time_totals();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(5),
+                Constraint::Length(5),
+                Constraint::Min(3),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    f.render_widget(Clear, area);
+
+    let created = stats.created();
+    let completed = stats.completed();
+    let charts = [
+        ("Created", &created, theme.pending),
+        ("Completed", &completed, theme.done),
+    ];
+    for (i, (name, data, style)) in charts.into_iter().enumerate() {
+        let title = format!(
+            "{} per day, last {} days: {}",
+            name,
+            stats::DAYS,
+            data.iter().sum::<u64>()
+        );
+        let sparkline = Sparkline::default()
+            .block(block(title, theme))
+            .data(data)
+            .style(style.remove_modifier(Modifier::CROSSED_OUT));
+        f.render_widget(sparkline, chunks[i]);
+    }
+
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let field = |name: &str, value: String| {
+        Spans::from(vec![
+            Span::styled(format!("{}: ", name), bold),
+            Span::raw(value),
+        ])
+    };
+    let tags = stats
+        .tags
+        .iter()
+        .map(|(tag, count)| format!("@{} {}", tag, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+    // The total, followed by the tasks with the most
+    let pomodoros = match stats.pomodoros.as_slice() {
+        [] => "0".to_string(),
+        tasks => format!(
+            "{} ({})",
+            stats.pomodoro_count(),
+            tasks
+                .iter()
+                .take(3)
+                .map(|(task, count)| format!("{} {}", task, count))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let or_dash = |text: String| {
+        if text.is_empty() {
+            "-".to_string()
+        } else {
+            text
+        }
+    };
+    let time_tags = time
+        .tags
+        .iter()
+        .map(|(tag, spent)| format!("@{} {}", tag, stats::format_duration(*spent)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let lines = vec![
+        field(
+            "Tasks",
+            format!(
+                "{} undone, {} pending, {} done ({:.0}% complete)",
+                stats.undone,
+                stats.pending,
+                stats.done,
+                app.completion_percentage()
+            ),
+        ),
+        field(
+            "Average time to completion",
+            stats
+                .average_completion
+                .map_or("-".to_string(), stats::format_duration),
+        ),
+        field("Streak", format!("{} days", stats.streak)),
+        field(
+            "Time tracked",
+            format!(
+                "{} ({} today)",
+                stats::format_duration(time.total),
+                stats::format_duration(time.on(Local::now().date_naive()))
+            ),
+        ),
+        field("Pomodoros", pomodoros),
+        field("Tags", or_dash(tags)),
+        field("Time per tag", or_dash(time_tags)),
+    ];
+    let summary = Paragraph::new(lines)
+        .block(block("Stats (Esc: close)", theme))
+        .wrap(Wrap { trim: false });
+    f.render_widget(summary, chunks[2]);
+}
+
+fn render_sort_menu<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    sort: Sort,
+    list_name: &str,
+    theme: &Theme,
+) {
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    let mut lines: Vec<Spans> = SortKey::ALL
+        .iter()
+        .map(|&key| {
+            let line = format!("  {}  {}", key.key(), key.name());
+            if key == sort.key {
+                Spans::from(Span::styled(format!("{} ✓", line), theme.highlight))
+            } else {
+                Spans::from(line)
+            }
+        })
+        .collect();
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(format!(
+        "  r  descending: {}",
+        on_off(sort.descending)
+    )));
+    lines.push(Spans::from(format!(
+        "  g  group by status: {}",
+        on_off(sort.group_by_status)
+    )));
+
+    let area = centered_rect(area, 40, 100);
+    let area = Rect {
+        height: (lines.len() as u16 + 2).min(area.height),
+        ..area
+    };
+    let menu =
+        Paragraph::new(lines).block(block(format!("Sort {} (Esc: close)", list_name), theme));
+    f.render_widget(Clear, area);
+    f.render_widget(menu, area);
+}
+
+fn render_snooze_menu<B: Backend>(f: &mut Frame<B>, area: Rect, theme: &Theme) {
+    let mut lines: Vec<Spans> = snooze_choices(Local::now())
+        .iter()
+        .map(|(key, name, until)| {
+            Spans::from(format!(
+                "  {}  {} ({})",
+                key,
+                name,
+                until.format("%a %Y-%m-%d %H:%M")
+            ))
+        })
+        .collect();
+    lines.push(Spans::from("  c  until a date..."));
+    lines.push(Spans::from(""));
+    lines.push(Spans::from("  u  wake up"));
+    lines.push(Spans::from("  s  show snoozed tasks"));
+
+    let area = centered_rect(area, 50, 100);
+    let area = Rect {
+        height: (lines.len() as u16 + 2).min(area.height),
+        ..area
+    };
+    let menu = Paragraph::new(lines).block(block("Snooze (Esc: close)", theme));
+    f.render_widget(Clear, area);
+    f.render_widget(menu, area);
+}
+
+fn status_style(status: &TaskStatus, theme: &Theme) -> Style {
+    match status {
+        TaskStatus::Undone => theme.undone,
+        TaskStatus::Pending => theme.pending,
+        TaskStatus::Done => theme.done,
+    }
+}
+
+/// A rectangle of the given percentage of `area`, centered in it.
+fn centered_rect(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let width = (area.width * percent_x / 100).max(40).min(area.width);
+    let height = (area.height * percent_y / 100).min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Hints like `d: delete, Space: toggle` for the bound actions among `actions`.
+fn key_hints(keymap: &Keymap, actions: &[Action]) -> String {
+    actions
+        .iter()
+        .map(|&action| (keymap.keys(action), action.description()))
+        .filter(|(keys, _)| !keys.is_empty())
+        .map(|(keys, description)| format!("{}: {}", keys, description))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A bordered block with a title, styled by the theme.
+fn block<'a>(title: impl Into<String>, theme: &Theme) -> Block<'a> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.border)
+        .title(Span::styled(title.into(), theme.title))
 }